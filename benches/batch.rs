@@ -0,0 +1,92 @@
+//! Measures how `align_spans_batch` scales with the number of threads.
+//!
+//! Run with `cargo bench --features rayon --bench batch`. The number of
+//! documents can be given as an argument.
+use std::time::{Duration, Instant};
+use textspan::{align_spans, align_spans_batch, Span};
+
+/// Deterministic pseudo-random numbers, to avoid a dependency.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, n: usize) -> usize {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1);
+        ((self.0 >> 33) % n as u64) as usize
+    }
+}
+
+/// A normalized text with the spans of its words, and an original text with
+/// different case, punctuation and spacing.
+fn document(rng: &mut Lcg) -> (Vec<Span>, String, String) {
+    const WORDS: &[&str] = &["tokyo", "tower", "is", "a", "café", "in", "minato", "東京"];
+    let (mut spans, mut text, mut original_text) = (vec![], String::new(), String::new());
+    let mut len = 0;
+    for _ in 0..200 {
+        let word = WORDS[rng.next(WORDS.len())];
+        let n = word.chars().count();
+        spans.push((len, len + n));
+        len += n + 1;
+        text.push_str(word);
+        text.push(' ');
+        match rng.next(4) {
+            0 => original_text.push_str(&word.to_uppercase()),
+            1 => original_text.push_str(&format!("\"{}\"", word)),
+            _ => original_text.push_str(word),
+        }
+        original_text.push_str(if rng.next(3) == 0 { "  " } else { " " });
+    }
+    (spans, text, original_text)
+}
+
+fn time<T>(f: impl Fn() -> T) -> Duration {
+    (0..3)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let n = std::env::args()
+        .skip(1)
+        .find_map(|x| x.parse().ok())
+        .unwrap_or(2000);
+    let mut rng = Lcg(0);
+    let docs: Vec<_> = (0..n).map(|_| document(&mut rng)).collect();
+    let expected: Vec<_> = docs
+        .iter()
+        .map(|(spans, text, original_text)| align_spans(spans, text, original_text))
+        .collect();
+    let base = time(|| {
+        docs.iter()
+            .map(|(spans, text, original_text)| align_spans(spans, text, original_text))
+            .collect::<Vec<_>>()
+    });
+    println!("{} documents", n);
+    println!("sequential: {:>10.2?}", base);
+    let max_threads = std::thread::available_parallelism().map_or(1, |x| x.get());
+    let mut threads = 1;
+    while threads <= max_threads {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        let batch = || align_spans_batch(docs.iter().map(|(s, t, o)| (s, t, o)));
+        assert_eq!(
+            pool.install(batch),
+            expected,
+            "output order must not change"
+        );
+        let elapsed = time(|| pool.install(batch));
+        println!(
+            "{:>2} threads: {:>10.2?} ({:.2}x)",
+            threads,
+            elapsed,
+            base.as_secs_f64() / elapsed.as_secs_f64()
+        );
+        threads *= 2;
+    }
+}
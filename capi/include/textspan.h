@@ -0,0 +1,246 @@
+#ifndef TEXTSPAN_H
+#define TEXTSPAN_H
+
+/* Generated by cbindgen from src/lib.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Result of every function. `TEXTSPAN_STATUS_OK` is 0.
+ */
+typedef enum TextspanStatus {
+  TEXTSPAN_STATUS_OK = 0,
+  /**
+   * A required pointer is null.
+   */
+  TEXTSPAN_STATUS_NULL_POINTER = 1,
+  /**
+   * A text is not valid UTF-8.
+   */
+  TEXTSPAN_STATUS_INVALID_UTF8 = 2,
+  /**
+   * A span whose start is greater than its end.
+   */
+  TEXTSPAN_STATUS_INVERTED_SPAN = 3,
+  /**
+   * A span out of the text it is defined on.
+   */
+  TEXTSPAN_STATUS_OUT_OF_BOUNDS = 4,
+  /**
+   * A byte offset not on a char boundary.
+   */
+  TEXTSPAN_STATUS_NOT_CHAR_BOUNDARY = 5,
+  /**
+   * The library panicked. This is a bug.
+   */
+  TEXTSPAN_STATUS_PANIC = 6,
+} TextspanStatus;
+
+/**
+ * Char mappings between two texts, computed once to convert spans
+ * repeatedly in both directions.
+ */
+typedef struct TextspanAlignment TextspanAlignment;
+
+/**
+ * A span `[start, end)` of char offsets.
+ */
+typedef struct TextspanSpan {
+  size_t start;
+  size_t end;
+} TextspanSpan;
+
+/**
+ * Groups of spans in CSR form: the spans of the `i`-th group are
+ * `spans[offsets[i]]` to `spans[offsets[i + 1] - 1]`, and `offsets` has
+ * `len + 1` items. Owned by the library; release it with
+ * `textspan_span_groups_free`.
+ */
+typedef struct TextspanSpanGroups {
+  size_t *offsets;
+  struct TextspanSpan *spans;
+  size_t len;
+} TextspanSpanGroups;
+
+/**
+ * A UTF-8 string of `len` bytes.
+ */
+typedef struct TextspanStr {
+  const char *ptr;
+  size_t len;
+} TextspanStr;
+
+/**
+ * Index range `[start, end)` of the target spans covering a span. `start` and
+ * `end` are insertion points unless `start_exact` and `end_exact` are true.
+ */
+typedef struct TextspanLiftedIndex {
+  size_t start;
+  bool start_exact;
+  size_t end;
+  bool end_exact;
+} TextspanLiftedIndex;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Returns a static, NUL-terminated description of `status`, a
+ * `TextspanStatus` value. Other values give "unknown status".
+ */
+const char *textspan_status_message(int status);
+
+/**
+ * Converts the spans defined in `text` to those defined in `original_text`.
+ *
+ * # Safety
+ *
+ * Every pointer must be valid for its length, and `out` for a write.
+ */
+enum TextspanStatus textspan_align_spans(const struct TextspanSpan *spans,
+                                         size_t spans_len,
+                                         const char *text,
+                                         size_t text_len,
+                                         const char *original_text,
+                                         size_t original_text_len,
+                                         struct TextspanSpanGroups *out);
+
+/**
+ * Returns the spans of `tokens` in `original_text`, based on the shortest
+ * edit script.
+ *
+ * # Safety
+ *
+ * Every pointer must be valid for its length, and `out` for a write.
+ */
+enum TextspanStatus textspan_get_original_spans(const struct TextspanStr *tokens,
+                                                size_t tokens_len,
+                                                const char *original_text,
+                                                size_t original_text_len,
+                                                struct TextspanSpanGroups *out);
+
+/**
+ * Removes overlapping spans, keeping the longest of spans starting at the
+ * same position. The remaining spans are written to `out`, which must have
+ * room for `spans_len` spans, and their number to `out_len`.
+ *
+ * # Safety
+ *
+ * Every pointer must be valid for its length, and `out_len` for a write.
+ */
+enum TextspanStatus textspan_remove_span_overlaps(const struct TextspanSpan *spans,
+                                                  size_t spans_len,
+                                                  struct TextspanSpan *out,
+                                                  size_t *out_len);
+
+/**
+ * Same as `textspan_remove_span_overlaps`, but writes the indices of the
+ * remaining spans.
+ *
+ * # Safety
+ *
+ * Every pointer must be valid for its length, and `out_len` for a write.
+ */
+enum TextspanStatus textspan_remove_span_overlaps_idx(const struct TextspanSpan *spans,
+                                                      size_t spans_len,
+                                                      size_t *out,
+                                                      size_t *out_len);
+
+/**
+ * Converts `span` to the index range of `target_spans` covering it.
+ * `target_spans` must be sorted and not overlapping.
+ *
+ * # Safety
+ *
+ * `target_spans` must be valid for its length, and `out` for a write.
+ */
+enum TextspanStatus textspan_lift_span_index(struct TextspanSpan span,
+                                             const struct TextspanSpan *target_spans,
+                                             size_t target_spans_len,
+                                             struct TextspanLiftedIndex *out);
+
+/**
+ * Same as `textspan_lift_span_index`, for `spans` sorted by their start.
+ * `out` must have room for `spans_len` items.
+ *
+ * # Safety
+ *
+ * Every pointer must be valid for its length.
+ */
+enum TextspanStatus textspan_lift_spans_index(const struct TextspanSpan *spans,
+                                              size_t spans_len,
+                                              const struct TextspanSpan *target_spans,
+                                              size_t target_spans_len,
+                                              struct TextspanLiftedIndex *out);
+
+/**
+ * Creates an alignment between `text` and `original_text`, written to
+ * `out`. Release it with `textspan_alignment_free`.
+ *
+ * # Safety
+ *
+ * Every pointer must be valid for its length, and `out` for a write.
+ */
+enum TextspanStatus textspan_alignment_new(const char *text,
+                                           size_t text_len,
+                                           const char *original_text,
+                                           size_t original_text_len,
+                                           struct TextspanAlignment **out);
+
+/**
+ * Releases an alignment. Does nothing if `alignment` is null.
+ *
+ * # Safety
+ *
+ * `alignment` must be null or created by `textspan_alignment_new`, and not
+ * be used afterwards.
+ */
+void textspan_alignment_free(struct TextspanAlignment *alignment);
+
+/**
+ * Converts the spans defined in the text of `alignment` to those defined in
+ * its original text.
+ *
+ * # Safety
+ *
+ * `alignment` must be created by `textspan_alignment_new`, `spans` must be
+ * valid for its length, and `out` for a write.
+ */
+enum TextspanStatus textspan_alignment_forward(const struct TextspanAlignment *alignment,
+                                               const struct TextspanSpan *spans,
+                                               size_t spans_len,
+                                               struct TextspanSpanGroups *out);
+
+/**
+ * Converts the spans defined in the original text of `alignment` to those
+ * defined in its text.
+ *
+ * # Safety
+ *
+ * Same as `textspan_alignment_forward`.
+ */
+enum TextspanStatus textspan_alignment_backward(const struct TextspanAlignment *alignment,
+                                                const struct TextspanSpan *spans,
+                                                size_t spans_len,
+                                                struct TextspanSpanGroups *out);
+
+/**
+ * Releases the buffers of `groups` and resets it. Does nothing if `groups`
+ * or its buffers are null.
+ *
+ * # Safety
+ *
+ * `groups` must be null or filled by this library, and not be released twice.
+ */
+void textspan_span_groups_free(struct TextspanSpanGroups *groups);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* TEXTSPAN_H */
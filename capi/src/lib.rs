@@ -0,0 +1,524 @@
+//! C bindings of textspan.
+//!
+//! Texts are UTF-8 buffers given with their length in bytes, and need not be
+//! NUL-terminated. Spans are char offsets, as in the Rust and Python APIs.
+//!
+//! Every function returns a [`TextspanStatus`] and writes its result through
+//! out pointers. Results of a known size are written to buffers owned by the
+//! caller; the others are allocated by the library and must be released with
+//! the matching `_free` function. A pointer may be null if its length is 0.
+use std::os::raw::{c_char, c_int};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::{ptr, slice};
+use textspanrs::Span;
+
+/// Result of every function. `TEXTSPAN_STATUS_OK` is 0.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextspanStatus {
+    Ok = 0,
+    /// A required pointer is null.
+    NullPointer = 1,
+    /// A text is not valid UTF-8.
+    InvalidUtf8 = 2,
+    /// A span whose start is greater than its end.
+    InvertedSpan = 3,
+    /// A span out of the text it is defined on.
+    OutOfBounds = 4,
+    /// A byte offset not on a char boundary.
+    NotCharBoundary = 5,
+    /// The library panicked. This is a bug.
+    Panic = 6,
+}
+
+impl From<textspanrs::Error> for TextspanStatus {
+    fn from(e: textspanrs::Error) -> Self {
+        match e {
+            textspanrs::Error::InvertedSpan { .. } => TextspanStatus::InvertedSpan,
+            textspanrs::Error::OutOfBounds { .. } => TextspanStatus::OutOfBounds,
+            textspanrs::Error::NotCharBoundary { .. } => TextspanStatus::NotCharBoundary,
+        }
+    }
+}
+
+/// A span `[start, end)` of char offsets.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextspanSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl From<TextspanSpan> for Span {
+    fn from(span: TextspanSpan) -> Self {
+        (span.start, span.end)
+    }
+}
+
+impl From<Span> for TextspanSpan {
+    fn from((start, end): Span) -> Self {
+        TextspanSpan { start, end }
+    }
+}
+
+/// A UTF-8 string of `len` bytes.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct TextspanStr {
+    pub ptr: *const c_char,
+    pub len: usize,
+}
+
+/// Groups of spans in CSR form: the spans of the `i`-th group are
+/// `spans[offsets[i]]` to `spans[offsets[i + 1] - 1]`, and `offsets` has
+/// `len + 1` items. Owned by the library; release it with
+/// `textspan_span_groups_free`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct TextspanSpanGroups {
+    pub offsets: *mut usize,
+    pub spans: *mut TextspanSpan,
+    pub len: usize,
+}
+
+impl TextspanSpanGroups {
+    fn new(groups: Vec<Vec<Span>>) -> Self {
+        let len = groups.len();
+        let mut offsets = Vec::with_capacity(len + 1);
+        offsets.push(0);
+        let mut spans = vec![];
+        for group in groups {
+            spans.extend(group.into_iter().map(TextspanSpan::from));
+            offsets.push(spans.len());
+        }
+        TextspanSpanGroups {
+            offsets: Box::into_raw(offsets.into_boxed_slice()) as *mut usize,
+            spans: Box::into_raw(spans.into_boxed_slice()) as *mut TextspanSpan,
+            len,
+        }
+    }
+}
+
+/// Index range `[start, end)` of the target spans covering a span. `start` and
+/// `end` are insertion points unless `start_exact` and `end_exact` are true.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextspanLiftedIndex {
+    pub start: usize,
+    pub start_exact: bool,
+    pub end: usize,
+    pub end_exact: bool,
+}
+
+type Lifted = (
+    std::result::Result<usize, usize>,
+    std::result::Result<usize, usize>,
+);
+
+impl From<Lifted> for TextspanLiftedIndex {
+    fn from((l, r): Lifted) -> Self {
+        TextspanLiftedIndex {
+            start: l.unwrap_or_else(|x| x),
+            start_exact: l.is_ok(),
+            end: r.unwrap_or_else(|x| x),
+            end_exact: r.is_ok(),
+        }
+    }
+}
+
+/// Char mappings between two texts, computed once to convert spans
+/// repeatedly in both directions.
+pub struct TextspanAlignment {
+    text_to_original: Vec<Vec<usize>>,
+    original_to_text: Vec<Vec<usize>>,
+}
+
+type Result<T> = std::result::Result<T, TextspanStatus>;
+
+/// Runs `f`, turning a panic into `TextspanStatus::Panic` so that it does not
+/// unwind into C.
+fn guard(f: impl FnOnce() -> Result<()>) -> TextspanStatus {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => TextspanStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => TextspanStatus::Panic,
+    }
+}
+
+unsafe fn as_slice<'a, T>(ptr: *const T, len: usize) -> Result<&'a [T]> {
+    if len == 0 {
+        Ok(&[])
+    } else if ptr.is_null() {
+        Err(TextspanStatus::NullPointer)
+    } else {
+        Ok(slice::from_raw_parts(ptr, len))
+    }
+}
+
+unsafe fn as_mut_slice<'a, T>(ptr: *mut T, len: usize) -> Result<&'a mut [T]> {
+    if len == 0 {
+        Ok(&mut [])
+    } else if ptr.is_null() {
+        Err(TextspanStatus::NullPointer)
+    } else {
+        Ok(slice::from_raw_parts_mut(ptr, len))
+    }
+}
+
+unsafe fn as_str<'a>(ptr: *const c_char, len: usize) -> Result<&'a str> {
+    let bytes = as_slice(ptr as *const u8, len)?;
+    std::str::from_utf8(bytes).map_err(|_| TextspanStatus::InvalidUtf8)
+}
+
+unsafe fn as_spans(ptr: *const TextspanSpan, len: usize) -> Result<Vec<Span>> {
+    Ok(as_slice(ptr, len)?.iter().map(|&x| x.into()).collect())
+}
+
+unsafe fn write<T>(out: *mut T, value: T) -> Result<()> {
+    if out.is_null() {
+        return Err(TextspanStatus::NullPointer);
+    }
+    ptr::write(out, value);
+    Ok(())
+}
+
+/// Returns a static, NUL-terminated description of `status`, a
+/// `TextspanStatus` value. Other values give "unknown status".
+#[no_mangle]
+pub extern "C" fn textspan_status_message(status: c_int) -> *const c_char {
+    let known = [
+        TextspanStatus::Ok,
+        TextspanStatus::NullPointer,
+        TextspanStatus::InvalidUtf8,
+        TextspanStatus::InvertedSpan,
+        TextspanStatus::OutOfBounds,
+        TextspanStatus::NotCharBoundary,
+        TextspanStatus::Panic,
+    ];
+    let message: &'static [u8] = match known.iter().find(|&&s| s as c_int == status) {
+        Some(TextspanStatus::Ok) => b"ok\0",
+        Some(TextspanStatus::NullPointer) => b"null pointer\0",
+        Some(TextspanStatus::InvalidUtf8) => b"invalid UTF-8\0",
+        Some(TextspanStatus::InvertedSpan) => b"span start is greater than its end\0",
+        Some(TextspanStatus::OutOfBounds) => b"span is out of bounds\0",
+        Some(TextspanStatus::NotCharBoundary) => b"offset is not on a char boundary\0",
+        Some(TextspanStatus::Panic) => b"internal error\0",
+        None => b"unknown status\0",
+    };
+    message.as_ptr() as *const c_char
+}
+
+/// Converts the spans defined in `text` to those defined in `original_text`.
+///
+/// # Safety
+///
+/// Every pointer must be valid for its length, and `out` for a write.
+#[no_mangle]
+pub unsafe extern "C" fn textspan_align_spans(
+    spans: *const TextspanSpan,
+    spans_len: usize,
+    text: *const c_char,
+    text_len: usize,
+    original_text: *const c_char,
+    original_text_len: usize,
+    out: *mut TextspanSpanGroups,
+) -> TextspanStatus {
+    guard(|| {
+        let spans = as_spans(spans, spans_len)?;
+        let text = as_str(text, text_len)?;
+        let original_text = as_str(original_text, original_text_len)?;
+        let ret = textspanrs::try_align_spans(&spans, text, original_text)?;
+        write(out, TextspanSpanGroups::new(ret))
+    })
+}
+
+/// Returns the spans of `tokens` in `original_text`, based on the shortest
+/// edit script.
+///
+/// # Safety
+///
+/// Every pointer must be valid for its length, and `out` for a write.
+#[no_mangle]
+pub unsafe extern "C" fn textspan_get_original_spans(
+    tokens: *const TextspanStr,
+    tokens_len: usize,
+    original_text: *const c_char,
+    original_text_len: usize,
+    out: *mut TextspanSpanGroups,
+) -> TextspanStatus {
+    guard(|| {
+        let tokens = as_slice(tokens, tokens_len)?
+            .iter()
+            .map(|x| as_str(x.ptr, x.len))
+            .collect::<Result<Vec<_>>>()?;
+        let original_text = as_str(original_text, original_text_len)?;
+        let ret = textspanrs::get_original_spans(&tokens, original_text);
+        write(out, TextspanSpanGroups::new(ret))
+    })
+}
+
+/// Removes overlapping spans, keeping the longest of spans starting at the
+/// same position. The remaining spans are written to `out`, which must have
+/// room for `spans_len` spans, and their number to `out_len`.
+///
+/// # Safety
+///
+/// Every pointer must be valid for its length, and `out_len` for a write.
+#[no_mangle]
+pub unsafe extern "C" fn textspan_remove_span_overlaps(
+    spans: *const TextspanSpan,
+    spans_len: usize,
+    out: *mut TextspanSpan,
+    out_len: *mut usize,
+) -> TextspanStatus {
+    guard(|| {
+        let ret = textspanrs::try_remove_span_overlaps(&as_spans(spans, spans_len)?)?;
+        let out = as_mut_slice(out, spans_len)?;
+        for (x, y) in out.iter_mut().zip(&ret) {
+            *x = (*y).into();
+        }
+        write(out_len, ret.len())
+    })
+}
+
+/// Same as `textspan_remove_span_overlaps`, but writes the indices of the
+/// remaining spans.
+///
+/// # Safety
+///
+/// Every pointer must be valid for its length, and `out_len` for a write.
+#[no_mangle]
+pub unsafe extern "C" fn textspan_remove_span_overlaps_idx(
+    spans: *const TextspanSpan,
+    spans_len: usize,
+    out: *mut usize,
+    out_len: *mut usize,
+) -> TextspanStatus {
+    guard(|| {
+        let ret = textspanrs::try_remove_span_overlaps_idx(&as_spans(spans, spans_len)?)?;
+        as_mut_slice(out, spans_len)?[..ret.len()].copy_from_slice(&ret);
+        write(out_len, ret.len())
+    })
+}
+
+/// Converts `span` to the index range of `target_spans` covering it.
+/// `target_spans` must be sorted and not overlapping.
+///
+/// # Safety
+///
+/// `target_spans` must be valid for its length, and `out` for a write.
+#[no_mangle]
+pub unsafe extern "C" fn textspan_lift_span_index(
+    span: TextspanSpan,
+    target_spans: *const TextspanSpan,
+    target_spans_len: usize,
+    out: *mut TextspanLiftedIndex,
+) -> TextspanStatus {
+    guard(|| {
+        let target_spans = as_spans(target_spans, target_spans_len)?;
+        let ret = textspanrs::try_lift_span_index(span.into(), &target_spans)?;
+        write(out, ret.into())
+    })
+}
+
+/// Same as `textspan_lift_span_index`, for `spans` sorted by their start.
+/// `out` must have room for `spans_len` items.
+///
+/// # Safety
+///
+/// Every pointer must be valid for its length.
+#[no_mangle]
+pub unsafe extern "C" fn textspan_lift_spans_index(
+    spans: *const TextspanSpan,
+    spans_len: usize,
+    target_spans: *const TextspanSpan,
+    target_spans_len: usize,
+    out: *mut TextspanLiftedIndex,
+) -> TextspanStatus {
+    guard(|| {
+        let spans = as_spans(spans, spans_len)?;
+        let target_spans = as_spans(target_spans, target_spans_len)?;
+        let ret = textspanrs::try_lift_spans_index(&spans, &target_spans)?;
+        let out = as_mut_slice(out, spans_len)?;
+        for (x, y) in out.iter_mut().zip(ret) {
+            *x = y.into();
+        }
+        Ok(())
+    })
+}
+
+/// Creates an alignment between `text` and `original_text`, written to
+/// `out`. Release it with `textspan_alignment_free`.
+///
+/// # Safety
+///
+/// Every pointer must be valid for its length, and `out` for a write.
+#[no_mangle]
+pub unsafe extern "C" fn textspan_alignment_new(
+    text: *const c_char,
+    text_len: usize,
+    original_text: *const c_char,
+    original_text_len: usize,
+    out: *mut *mut TextspanAlignment,
+) -> TextspanStatus {
+    guard(|| {
+        let text = as_str(text, text_len)?;
+        let original_text = as_str(original_text, original_text_len)?;
+        let (text_to_original, original_to_text) = tokenizations::get_charmap(text, original_text);
+        let alignment = TextspanAlignment {
+            text_to_original,
+            original_to_text,
+        };
+        write(out, Box::into_raw(Box::new(alignment)))
+    })
+}
+
+/// Releases an alignment. Does nothing if `alignment` is null.
+///
+/// # Safety
+///
+/// `alignment` must be null or created by `textspan_alignment_new`, and not
+/// be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn textspan_alignment_free(alignment: *mut TextspanAlignment) {
+    if !alignment.is_null() {
+        drop(Box::from_raw(alignment));
+    }
+}
+
+unsafe fn align(
+    mapping: Option<&Vec<Vec<usize>>>,
+    spans: *const TextspanSpan,
+    spans_len: usize,
+    out: *mut TextspanSpanGroups,
+) -> TextspanStatus {
+    guard(|| {
+        let mapping = mapping.ok_or(TextspanStatus::NullPointer)?;
+        let spans = as_spans(spans, spans_len)?;
+        let ret = textspanrs::try_align_spans_by_mapping(&spans, mapping)?;
+        write(out, TextspanSpanGroups::new(ret))
+    })
+}
+
+/// Converts the spans defined in the text of `alignment` to those defined in
+/// its original text.
+///
+/// # Safety
+///
+/// `alignment` must be created by `textspan_alignment_new`, `spans` must be
+/// valid for its length, and `out` for a write.
+#[no_mangle]
+pub unsafe extern "C" fn textspan_alignment_forward(
+    alignment: *const TextspanAlignment,
+    spans: *const TextspanSpan,
+    spans_len: usize,
+    out: *mut TextspanSpanGroups,
+) -> TextspanStatus {
+    let mapping = alignment.as_ref().map(|x| &x.text_to_original);
+    align(mapping, spans, spans_len, out)
+}
+
+/// Converts the spans defined in the original text of `alignment` to those
+/// defined in its text.
+///
+/// # Safety
+///
+/// Same as `textspan_alignment_forward`.
+#[no_mangle]
+pub unsafe extern "C" fn textspan_alignment_backward(
+    alignment: *const TextspanAlignment,
+    spans: *const TextspanSpan,
+    spans_len: usize,
+    out: *mut TextspanSpanGroups,
+) -> TextspanStatus {
+    let mapping = alignment.as_ref().map(|x| &x.original_to_text);
+    align(mapping, spans, spans_len, out)
+}
+
+/// Releases the buffers of `groups` and resets it. Does nothing if `groups`
+/// or its buffers are null.
+///
+/// # Safety
+///
+/// `groups` must be null or filled by this library, and not be released twice.
+#[no_mangle]
+pub unsafe extern "C" fn textspan_span_groups_free(groups: *mut TextspanSpanGroups) {
+    let groups = match groups.as_mut() {
+        Some(groups) if !groups.offsets.is_null() => groups,
+        _ => return,
+    };
+    let offsets = Box::from_raw(ptr::slice_from_raw_parts_mut(
+        groups.offsets,
+        groups.len + 1,
+    ));
+    drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+        groups.spans,
+        offsets[groups.len],
+    )));
+    groups.offsets = ptr::null_mut();
+    groups.spans = ptr::null_mut();
+    groups.len = 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn align_spans_and_free() {
+        let spans = [TextspanSpan { start: 0, end: 3 }];
+        let (text, original_text) = ("foo", "F.OO");
+        let mut out = TextspanSpanGroups {
+            offsets: ptr::null_mut(),
+            spans: ptr::null_mut(),
+            len: 0,
+        };
+        unsafe {
+            let status = textspan_align_spans(
+                spans.as_ptr(),
+                spans.len(),
+                text.as_ptr() as *const c_char,
+                text.len(),
+                original_text.as_ptr() as *const c_char,
+                original_text.len(),
+                &mut out,
+            );
+            assert_eq!(status, TextspanStatus::Ok);
+            assert_eq!(slice::from_raw_parts(out.offsets, 2), [0, 2]);
+            assert_eq!(
+                slice::from_raw_parts(out.spans, 2),
+                [
+                    TextspanSpan { start: 0, end: 1 },
+                    TextspanSpan { start: 2, end: 4 }
+                ]
+            );
+            textspan_span_groups_free(&mut out);
+        }
+        assert!(out.offsets.is_null());
+    }
+
+    #[test]
+    fn errors() {
+        let spans = [TextspanSpan { start: 2, end: 1 }];
+        let mut out_len = 0;
+        let mut out = [TextspanSpan { start: 0, end: 0 }];
+        unsafe {
+            let status =
+                textspan_remove_span_overlaps(spans.as_ptr(), 1, out.as_mut_ptr(), &mut out_len);
+            assert_eq!(status, TextspanStatus::InvertedSpan);
+            let status =
+                textspan_remove_span_overlaps(ptr::null(), 1, out.as_mut_ptr(), &mut out_len);
+            assert_eq!(status, TextspanStatus::NullPointer);
+            let mut alignment = ptr::null_mut();
+            let text = [0xffu8];
+            let status = textspan_alignment_new(
+                text.as_ptr() as *const c_char,
+                1,
+                ptr::null(),
+                0,
+                &mut alignment,
+            );
+            assert_eq!(status, TextspanStatus::InvalidUtf8);
+        }
+    }
+}
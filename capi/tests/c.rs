@@ -0,0 +1,35 @@
+//! Compiles `tests/test.c` against `include/textspan.h` and the shared
+//! library, and runs it. Requires a C compiler as `cc`, or `$CC`.
+#![cfg(unix)]
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+#[test]
+fn c_tests() {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // the library is built next to this test, in target/<profile>/deps
+    let exe = env::current_exe().unwrap();
+    let lib_dir = exe.parent().unwrap();
+    let out = lib_dir.join("textspan_c_tests");
+    let cc = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(cc)
+        .args(["-std=c99", "-Wall", "-Werror", "-o"])
+        .arg(&out)
+        .arg(manifest_dir.join("tests/test.c"))
+        .arg("-I")
+        .arg(manifest_dir.join("include"))
+        .arg("-L")
+        .arg(lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-ltextspan")
+        .status()
+        .expect("failed to run the C compiler");
+    assert!(status.success());
+    let output = Command::new(&out).output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
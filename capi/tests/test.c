@@ -0,0 +1,121 @@
+#include <assert.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "textspan.h"
+
+#define LEN(x) (sizeof(x) / sizeof((x)[0]))
+#define CHECK(expr) assert((expr) == TEXTSPAN_STATUS_OK)
+
+static void test_align_spans(void) {
+  const char *text = "foobarbaz";
+  const char *original_text = "FOo.BåR baZ";
+  TextspanSpan spans[] = {{0, 3}, {3, 6}};
+  TextspanSpanGroups out;
+  CHECK(textspan_align_spans(spans, LEN(spans), text, strlen(text), original_text,
+                             strlen(original_text), &out));
+  assert(out.len == 2);
+  assert(out.offsets[0] == 0 && out.offsets[1] == 1 && out.offsets[2] == 2);
+  assert(out.spans[0].start == 0 && out.spans[0].end == 3);
+  /* spans are char offsets: "å" is one char but two bytes */
+  assert(out.spans[1].start == 4 && out.spans[1].end == 7);
+  textspan_span_groups_free(&out);
+  assert(out.offsets == NULL && out.len == 0);
+  textspan_span_groups_free(&out);
+}
+
+static void test_get_original_spans(void) {
+  TextspanStr tokens[] = {{"a", 1}, {"la", 2}, {"gorge", 5}};
+  const char *original_text = "à  LA    gorge";
+  TextspanSpanGroups out;
+  CHECK(textspan_get_original_spans(tokens, LEN(tokens), original_text,
+                                    strlen(original_text), &out));
+  assert(out.len == 3 && out.offsets[3] == 3);
+  assert(out.spans[0].start == 0 && out.spans[0].end == 1);
+  assert(out.spans[1].start == 3 && out.spans[1].end == 5);
+  assert(out.spans[2].start == 9 && out.spans[2].end == 14);
+  textspan_span_groups_free(&out);
+}
+
+static void test_alignment(void) {
+  const char *text = "foobarbaz";
+  const char *original_text = "FOo.BåR baZ";
+  TextspanAlignment *alignment = NULL;
+  CHECK(textspan_alignment_new(text, strlen(text), original_text, strlen(original_text),
+                               &alignment));
+  TextspanSpan spans[] = {{3, 6}};
+  TextspanSpanGroups out;
+  CHECK(textspan_alignment_forward(alignment, spans, LEN(spans), &out));
+  assert(out.len == 1 && out.spans[0].start == 4 && out.spans[0].end == 7);
+  textspan_span_groups_free(&out);
+
+  TextspanSpan original_spans[] = {{0, 7}};
+  CHECK(textspan_alignment_backward(alignment, original_spans, LEN(original_spans), &out));
+  assert(out.len == 1 && out.spans[0].start == 0 && out.spans[0].end == 6);
+  textspan_span_groups_free(&out);
+
+  TextspanSpan invalid[] = {{0, 10}};
+  assert(textspan_alignment_forward(alignment, invalid, LEN(invalid), &out) ==
+         TEXTSPAN_STATUS_OUT_OF_BOUNDS);
+  textspan_alignment_free(alignment);
+  textspan_alignment_free(NULL);
+}
+
+static void test_remove_span_overlaps(void) {
+  TextspanSpan spans[] = {{0, 2}, {0, 3}, {2, 4}, {5, 7}};
+  TextspanSpan out[LEN(spans)];
+  size_t out_len;
+  CHECK(textspan_remove_span_overlaps(spans, LEN(spans), out, &out_len));
+  assert(out_len == 2);
+  assert(out[0].start == 0 && out[0].end == 3 && out[1].start == 5 && out[1].end == 7);
+
+  size_t indices[LEN(spans)];
+  CHECK(textspan_remove_span_overlaps_idx(spans, LEN(spans), indices, &out_len));
+  assert(out_len == 2 && indices[0] == 1 && indices[1] == 3);
+}
+
+static void test_lift_span_index(void) {
+  TextspanSpan targets[] = {{0, 3}, {3, 4}, {4, 9}, {9, 12}};
+  TextspanSpan span = {3, 10};
+  TextspanLiftedIndex out;
+  CHECK(textspan_lift_span_index(span, targets, LEN(targets), &out));
+  assert(out.start == 1 && out.start_exact && out.end == 4 && !out.end_exact);
+
+  TextspanSpan spans[] = {{0, 3}, {3, 10}};
+  TextspanLiftedIndex outs[LEN(spans)];
+  CHECK(textspan_lift_spans_index(spans, LEN(spans), targets, LEN(targets), outs));
+  assert(outs[0].start == 0 && outs[0].end == 1 && outs[1].start == 1 && outs[1].end == 4);
+}
+
+static void test_errors(void) {
+  TextspanSpan inverted[] = {{2, 1}};
+  TextspanSpanGroups out;
+  TextspanStatus status = textspan_align_spans(inverted, 1, "foo", 3, "foo", 3, &out);
+  assert(status == TEXTSPAN_STATUS_INVERTED_SPAN);
+  assert(strlen(textspan_status_message(status)) > 0);
+  assert(strcmp(textspan_status_message(TEXTSPAN_STATUS_OK), "ok") == 0);
+  assert(strcmp(textspan_status_message(-1), "unknown status") == 0);
+  assert(strcmp(textspan_status_message(100), "unknown status") == 0);
+  TextspanSpan out_of_bounds[] = {{0, 4}};
+  assert(textspan_align_spans(out_of_bounds, 1, "foo", 3, "foo", 3, &out) ==
+         TEXTSPAN_STATUS_OUT_OF_BOUNDS);
+  assert(textspan_align_spans(NULL, 1, "foo", 3, "foo", 3, &out) ==
+         TEXTSPAN_STATUS_NULL_POINTER);
+  assert(textspan_align_spans(NULL, 0, "\xff", 1, "foo", 3, &out) ==
+         TEXTSPAN_STATUS_INVALID_UTF8);
+  /* empty inputs need no buffer */
+  CHECK(textspan_align_spans(NULL, 0, NULL, 0, NULL, 0, &out));
+  assert(out.len == 0);
+  textspan_span_groups_free(&out);
+}
+
+int main(void) {
+  test_align_spans();
+  test_get_original_spans();
+  test_alignment();
+  test_remove_span_overlaps();
+  test_lift_span_index();
+  test_errors();
+  puts("ok");
+  return 0;
+}
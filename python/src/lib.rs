@@ -1,9 +1,362 @@
+use numpy::ndarray::Array2;
+use numpy::{IntoPyArray, PyArray1, PyArray2, PyReadonlyArray1, PyReadonlyArray2};
+use pyo3::basic::CompareOp;
+use pyo3::exceptions::{PyTypeError, PyValueError};
 use pyo3::prelude::*;
+use pyo3::types::{PyString, PyTuple};
 use textspanrs::Span;
 
+// the macro of pyo3 0.17 checks a cfg unknown to recent compilers
+#[allow(unexpected_cfgs)]
+mod exceptions {
+    use pyo3::exceptions::PyValueError;
+
+    pyo3::create_exception!(
+        textspan,
+        InvalidSpanError,
+        PyValueError,
+        "Raised for a span that is inverted or out of the text it is defined on."
+    );
+}
+use exceptions::InvalidSpanError;
+
+fn invalid_span(e: textspanrs::Error) -> PyErr {
+    InvalidSpanError::new_err(e.to_string())
+}
+
+/// Extracts the items of any iterable but a `str` with `f`.
+fn extract_iter<'a, T>(ob: &'a PyAny, f: impl Fn(&'a PyAny) -> PyResult<T>) -> PyResult<Vec<T>> {
+    if ob.is_instance_of::<PyString>()? {
+        return Err(PyTypeError::new_err("expected an iterable, not `str`"));
+    }
+    let mut ret = Vec::with_capacity(ob.len().unwrap_or(0));
+    for x in ob.iter()? {
+        ret.push(f(x?)?);
+    }
+    Ok(ret)
+}
+
+/// Any iterable of `T` but a `str`, e.g. a list, a tuple, a generator or a
+/// NumPy array. Unlike `Vec<T>`, this does not require a sequence.
+struct Seq<T>(Vec<T>);
+
+impl<'a, T: FromPyObject<'a>> FromPyObject<'a> for Seq<T> {
+    fn extract(ob: &'a PyAny) -> PyResult<Self> {
+        extract_iter(ob, PyAny::extract).map(Seq)
+    }
+}
+
+impl<T> AsRef<[T]> for Seq<T> {
+    fn as_ref(&self) -> &[T] {
+        &self.0
+    }
+}
+
+/// A span given as any iterable of two ints, e.g. a tuple, a list or a row
+/// of a NumPy array.
+struct PySpan(Span);
+
+impl<'a> FromPyObject<'a> for PySpan {
+    fn extract(ob: &'a PyAny) -> PyResult<Self> {
+        if let Ok(span) = ob.extract() {
+            return Ok(PySpan(span));
+        }
+        match Seq::<usize>::extract(ob)?.0[..] {
+            [l, r] => Ok(PySpan((l, r))),
+            _ => Err(PyTypeError::new_err("a span must have two items")),
+        }
+    }
+}
+
+/// Spans given as any iterable of `PySpan`s.
+struct Spans(Vec<Span>);
+
+impl<'a> FromPyObject<'a> for Spans {
+    fn extract(ob: &'a PyAny) -> PyResult<Self> {
+        extract_iter(ob, |x| Ok(PySpan::extract(x)?.0)).map(Spans)
+    }
+}
+
+/// Reads an `(N, 2)` array of spans.
+fn spans_from_numpy(spans: PyReadonlyArray2<i64>) -> PyResult<Vec<Span>> {
+    let spans = spans.as_array();
+    if spans.ncols() != 2 {
+        return Err(PyValueError::new_err("spans must have shape (N, 2)"));
+    }
+    spans
+        .rows()
+        .into_iter()
+        .map(|row| {
+            if row[0] < 0 || row[1] < 0 {
+                return Err(PyValueError::new_err("spans must be non-negative"));
+            }
+            Ok((row[0] as usize, row[1] as usize))
+        })
+        .collect()
+}
+
+fn spans_to_numpy<'py>(py: Python<'py>, spans: &[Span]) -> &'py PyArray2<i64> {
+    let flat = spans
+        .iter()
+        .flat_map(|&(l, r)| [l as i64, r as i64])
+        .collect();
+    Array2::from_shape_vec((spans.len(), 2), flat)
+        .unwrap()
+        .into_pyarray(py)
+}
+
+/// Converts nested spans to CSR-style `(offsets, spans)`, where the spans of
+/// the `i`-th element are `spans[offsets[i]:offsets[i + 1]]`.
+fn nested_spans_to_numpy<'py>(
+    py: Python<'py>,
+    nested: Vec<Vec<Span>>,
+) -> (&'py PyArray1<i64>, &'py PyArray2<i64>) {
+    let mut offsets = Vec::with_capacity(nested.len() + 1);
+    offsets.push(0);
+    for x in &nested {
+        offsets.push(offsets.last().unwrap() + x.len() as i64);
+    }
+    let flat: Vec<Span> = nested.into_iter().flatten().collect();
+    (offsets.into_pyarray(py), spans_to_numpy(py, &flat))
+}
+
+/// Reads a CSR-style mapping, where the `i`-th char maps to
+/// `indices[offsets[i]:offsets[i + 1]]`.
+fn mapping_from_numpy(
+    offsets: PyReadonlyArray1<i64>,
+    indices: PyReadonlyArray1<i64>,
+) -> PyResult<Vec<Vec<usize>>> {
+    let offsets = offsets.as_array();
+    let indices = indices.as_array();
+    let invalid = || PyValueError::new_err("invalid mapping offsets or indices");
+    if offsets.first() != Some(&0) || offsets.last() != Some(&(indices.len() as i64)) {
+        return Err(invalid());
+    }
+    offsets
+        .windows(2)
+        .into_iter()
+        .map(|w| {
+            if w[0] > w[1] {
+                return Err(invalid());
+            }
+            indices
+                .slice(numpy::ndarray::s![w[0] as usize..w[1] as usize])
+                .iter()
+                .map(|&x| {
+                    if x < 0 {
+                        Err(invalid())
+                    } else {
+                        Ok(x as usize)
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Alignment between `text` and `original_text`.
+///
+/// The char mapping is computed once, so that spans can be converted
+/// repeatedly in both directions without recomputing it.
+///
+/// Examples:
+///
+///     >>> import textspan
+///     >>> alignment = textspan.Alignment("foobarbaz", "FOo.BåR baZ")
+///     >>> alignment.forward([(0, 3), (3, 6)])
+///     [[(0, 3)], [(4, 7)]]
+///     >>> alignment.backward([(0, 7)])
+///     [[(0, 6)]]
+#[pyclass(module = "textspan")]
+#[pyo3(text_signature = "(text, original_text)")]
+struct Alignment {
+    text: String,
+    original_text: String,
+    text_to_original: Vec<Vec<usize>>,
+    original_to_text: Vec<Vec<usize>>,
+}
+
+#[pymethods]
+impl Alignment {
+    #[new]
+    fn new(py: Python, text: String, original_text: String) -> Self {
+        let (text_to_original, original_to_text) =
+            py.allow_threads(|| tokenizations::get_charmap(&text, &original_text));
+        Alignment {
+            text,
+            original_text,
+            text_to_original,
+            original_to_text,
+        }
+    }
+
+    #[getter]
+    fn text(&self) -> &str {
+        &self.text
+    }
+
+    #[getter]
+    fn original_text(&self) -> &str {
+        &self.original_text
+    }
+
+    /// Char mapping from `text` to `original_text`.
+    #[getter]
+    fn text_to_original(&self) -> Vec<Vec<usize>> {
+        self.text_to_original.clone()
+    }
+
+    /// Char mapping from `original_text` to `text`.
+    #[getter]
+    fn original_to_text(&self) -> Vec<Vec<usize>> {
+        self.original_to_text.clone()
+    }
+
+    /// Converts the spans defined in `text` to those defined in `original_text`,
+    /// as `align_spans` does.
+    #[pyo3(text_signature = "($self, spans)")]
+    fn forward(&self, spans: Spans) -> PyResult<Vec<Vec<Span>>> {
+        textspanrs::try_align_spans_by_mapping(&spans.0, &self.text_to_original)
+            .map_err(invalid_span)
+    }
+
+    /// Converts the spans defined in `original_text` to those defined in `text`.
+    #[pyo3(text_signature = "($self, spans)")]
+    fn backward(&self, spans: Spans) -> PyResult<Vec<Vec<Span>>> {
+        textspanrs::try_align_spans_by_mapping(&spans.0, &self.original_to_text)
+            .map_err(invalid_span)
+    }
+
+    fn __getnewargs__(&self) -> (&str, &str) {
+        (&self.text, &self.original_text)
+    }
+
+    fn __repr__(&self, py: Python) -> PyResult<String> {
+        Ok(format!(
+            "Alignment(text={}, original_text={})",
+            PyString::new(py, &self.text).repr()?,
+            PyString::new(py, &self.original_text).repr()?
+        ))
+    }
+}
+
+/// Index range of `target_spans` covering a span, as returned by
+/// `lift_span_index`.
+///
+/// `start_exact` (`end_exact`) is true if the span starts (ends) exactly at a
+/// boundary of `target_spans`. It behaves as the nested tuple
+/// `((start, start_exact), (end, end_exact))`, as returned by previous
+/// versions.
+///
+/// Examples:
+///
+///     >>> import textspan
+///     >>> index = textspan.lift_span_index((3, 10), [(0, 3), (3, 4), (4, 9), (9, 12)])
+///     >>> index
+///     LiftedIndex(start=1, start_exact=True, end=4, end_exact=False)
+///     >>> (index.start, index.end)
+///     (1, 4)
+///     >>> (start, start_exact), (end, end_exact) = index
+///     >>> (start, end)
+///     (1, 4)
+#[pyclass(module = "textspan")]
+#[pyo3(text_signature = "(start, start_exact, end, end_exact)")]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct LiftedIndex {
+    #[pyo3(get)]
+    start: usize,
+    #[pyo3(get)]
+    start_exact: bool,
+    #[pyo3(get)]
+    end: usize,
+    #[pyo3(get)]
+    end_exact: bool,
+}
+
+impl LiftedIndex {
+    fn from_result((l, r): (Result<usize, usize>, Result<usize, usize>)) -> Self {
+        LiftedIndex {
+            start: l.unwrap_or_else(|x| x),
+            start_exact: l.is_ok(),
+            end: r.unwrap_or_else(|x| x),
+            end_exact: r.is_ok(),
+        }
+    }
+
+    fn to_tuple<'py>(self, py: Python<'py>) -> &'py PyTuple {
+        PyTuple::new(
+            py,
+            [
+                (self.start, self.start_exact).to_object(py),
+                (self.end, self.end_exact).to_object(py),
+            ],
+        )
+    }
+}
+
+#[pymethods]
+impl LiftedIndex {
+    #[new]
+    fn new(start: usize, start_exact: bool, end: usize, end_exact: bool) -> Self {
+        LiftedIndex {
+            start,
+            start_exact,
+            end,
+            end_exact,
+        }
+    }
+
+    fn __len__(&self) -> usize {
+        2
+    }
+
+    fn __getitem__<'py>(&self, py: Python<'py>, index: &PyAny) -> PyResult<&'py PyAny> {
+        PyAny::get_item(self.to_tuple(py), index)
+    }
+
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<&'py PyAny> {
+        self.to_tuple(py).call_method0("__iter__")
+    }
+
+    fn __richcmp__<'py>(
+        &self,
+        py: Python<'py>,
+        other: &PyAny,
+        op: CompareOp,
+    ) -> PyResult<&'py PyAny> {
+        let other = match other.extract::<LiftedIndex>() {
+            Ok(x) => x.to_tuple(py),
+            Err(_) => other,
+        };
+        self.to_tuple(py).rich_compare(other, op)
+    }
+
+    fn __hash__(&self, py: Python) -> PyResult<isize> {
+        self.to_tuple(py).hash()
+    }
+
+    fn __getnewargs__(&self) -> (usize, bool, usize, bool) {
+        (self.start, self.start_exact, self.end, self.end_exact)
+    }
+
+    fn __repr__(&self) -> String {
+        let b = |x: bool| if x { "True" } else { "False" };
+        format!(
+            "LiftedIndex(start={}, start_exact={}, end={}, end_exact={})",
+            self.start,
+            b(self.start_exact),
+            self.end,
+            b(self.end_exact)
+        )
+    }
+}
+
 #[pymodule]
-fn textspan(_py: Python, m: &PyModule) -> PyResult<()> {
+fn textspan(py: Python, m: &PyModule) -> PyResult<()> {
     m.add("__version__", "0.5.7")?;
+    m.add_class::<Alignment>()?;
+    m.add_class::<LiftedIndex>()?;
+    m.add("InvalidSpanError", py.get_type::<InvalidSpanError>())?;
 
     /// Converts the spans defined in `text` to those defined in `original_text`.
     ///
@@ -22,11 +375,71 @@ fn textspan(_py: Python, m: &PyModule) -> PyResult<()> {
     #[pyo3(text_signature = "(spans, text, original_text)")]
     pub fn align_spans(
         _py: Python,
-        spans: Vec<Span>,
+        spans: Spans,
         text: &str,
         original_text: &str,
     ) -> PyResult<Vec<Vec<Span>>> {
-        Ok(textspanrs::align_spans(&spans, text, original_text))
+        textspanrs::try_align_spans(&spans.0, text, original_text).map_err(invalid_span)
+    }
+
+    /// Batched version of `align_spans`, for many documents at once.
+    ///
+    /// The documents are processed in parallel without holding the GIL, and
+    /// the results are returned in the order of the input.
+    ///
+    /// Examples:
+    ///
+    ///     >>> import textspan
+    ///     >>> textspan.align_spans_batch([[(0, 3)], [(1, 2)]], ["foo", "ab"], ["FOO", "a b"])
+    ///     [[[(0, 3)]], [[(2, 3)]]]
+    #[pyfn(m)]
+    #[pyo3(text_signature = "(spans, texts, original_texts)")]
+    pub fn align_spans_batch(
+        py: Python,
+        spans: Seq<Spans>,
+        texts: Seq<&str>,
+        original_texts: Seq<&str>,
+    ) -> PyResult<Vec<Vec<Vec<Span>>>> {
+        let (spans, texts, original_texts) = (spans.0, texts.0, original_texts.0);
+        if spans.len() != texts.len() || texts.len() != original_texts.len() {
+            return Err(PyValueError::new_err(
+                "`spans`, `texts` and `original_texts` must have the same length",
+            ));
+        }
+        let docs = spans.iter().zip(texts).zip(original_texts);
+        py.allow_threads(|| {
+            textspanrs::try_align_spans_batch(
+                docs.map(|((spans, text), original_text)| (&spans.0, text, original_text)),
+            )
+        })
+        .map_err(invalid_span)
+    }
+
+    /// NumPy version of `align_spans`.
+    ///
+    /// `spans` is an `(N, 2)` int64 array. Returns `(offsets, spans)`, where
+    /// the aligned spans of the `i`-th span are `spans[offsets[i]:offsets[i + 1]]`.
+    ///
+    /// Examples:
+    ///
+    ///     >>> import numpy as np
+    ///     >>> import textspan
+    ///     >>> offsets, spans = textspan.align_spans_numpy(np.array([[0, 3], [3, 6]]), "foobarbaz", "FOo.BåR baZ")
+    ///     >>> offsets.tolist(), spans.tolist()
+    ///     ([0, 1, 2], [[0, 3], [4, 7]])
+    #[pyfn(m)]
+    #[pyo3(text_signature = "(spans, text, original_text)")]
+    pub fn align_spans_numpy<'py>(
+        py: Python<'py>,
+        spans: PyReadonlyArray2<i64>,
+        text: &str,
+        original_text: &str,
+    ) -> PyResult<(&'py PyArray1<i64>, &'py PyArray2<i64>)> {
+        let spans = spans_from_numpy(spans)?;
+        let ret = py
+            .allow_threads(|| textspanrs::try_align_spans(&spans, text, original_text))
+            .map_err(invalid_span)?;
+        Ok(nested_spans_to_numpy(py, ret))
     }
 
     /// Converts the spans by the given `mapping`.
@@ -35,7 +448,7 @@ fn textspan(_py: Python, m: &PyModule) -> PyResult<()> {
     /// necessarily surjective, not injective, not even a methematical map -
     /// some character in `textA` may not have a correspondence in `textB`,
     /// or may have multiple correspondences in `textB`. Thus, you should
-    /// provide `mapping` as `List[List[Tuple[int,int]]]`.
+    /// provide `mapping` as `List[List[int]]`.
     ///
     /// Examples:
     ///     >>> import textspan
@@ -47,10 +460,40 @@ fn textspan(_py: Python, m: &PyModule) -> PyResult<()> {
     #[pyo3(text_signature = "(spans, mapping)")]
     pub fn align_spans_by_mapping(
         _py: Python,
-        spans: Vec<Span>,
-        mapping: Vec<Vec<usize>>,
+        spans: Spans,
+        mapping: Seq<Seq<usize>>,
     ) -> PyResult<Vec<Vec<Span>>> {
-        Ok(textspanrs::align_spans_by_mapping(&spans, &mapping))
+        textspanrs::try_align_spans_by_mapping(&spans.0, &mapping.0).map_err(invalid_span)
+    }
+
+    /// NumPy version of `align_spans_by_mapping`.
+    ///
+    /// `spans` is an `(N, 2)` int64 array, and the `i`-th char maps to
+    /// `mapping_indices[mapping_offsets[i]:mapping_offsets[i + 1]]`. Returns
+    /// `(offsets, spans)` as `align_spans_numpy` does.
+    ///
+    /// Examples:
+    ///     >>> import numpy as np
+    ///     >>> import textspan
+    ///     >>> offsets, spans = textspan.align_spans_by_mapping_numpy(
+    ///     ...     np.array([[0, 2], [3, 4]]), np.array([0, 2, 2, 3, 6]), np.array([0, 1, 2, 4, 5, 6])
+    ///     ... )
+    ///     >>> offsets.tolist(), spans.tolist()
+    ///     ([0, 1, 2], [[0, 2], [4, 7]])
+    #[pyfn(m)]
+    #[pyo3(text_signature = "(spans, mapping_offsets, mapping_indices)")]
+    pub fn align_spans_by_mapping_numpy<'py>(
+        py: Python<'py>,
+        spans: PyReadonlyArray2<i64>,
+        mapping_offsets: PyReadonlyArray1<i64>,
+        mapping_indices: PyReadonlyArray1<i64>,
+    ) -> PyResult<(&'py PyArray1<i64>, &'py PyArray2<i64>)> {
+        let spans = spans_from_numpy(spans)?;
+        let mapping = mapping_from_numpy(mapping_offsets, mapping_indices)?;
+        let ret = py
+            .allow_threads(|| textspanrs::try_align_spans_by_mapping(&spans, &mapping))
+            .map_err(invalid_span)?;
+        Ok(nested_spans_to_numpy(py, ret))
     }
 
     /// Returns the span indices of `original_text` from the tokens based on the shortest edit script (SES).
@@ -68,10 +511,58 @@ fn textspan(_py: Python, m: &PyModule) -> PyResult<()> {
     #[pyo3(text_signature = "(tokens, original_text)")]
     pub fn get_original_spans(
         _py: Python,
-        tokens: Vec<&str>,
+        tokens: Seq<&str>,
         original_text: &str,
     ) -> PyResult<Vec<Vec<Span>>> {
-        Ok(textspanrs::get_original_spans(&tokens, original_text))
+        Ok(textspanrs::get_original_spans(&tokens.0, original_text))
+    }
+
+    /// NumPy version of `get_original_spans`. Returns `(offsets, spans)` as
+    /// `align_spans_numpy` does.
+    ///
+    /// Examples:
+    ///     >>> import textspan
+    ///     >>> offsets, spans = textspan.get_original_spans_numpy(["foo", "bar"], "FO.o  BåR")
+    ///     >>> offsets.tolist(), spans.tolist()
+    ///     ([0, 2, 3], [[0, 2], [3, 4], [6, 9]])
+    ///
+    #[pyfn(m)]
+    #[pyo3(text_signature = "(tokens, original_text)")]
+    pub fn get_original_spans_numpy<'py>(
+        py: Python<'py>,
+        tokens: Seq<&str>,
+        original_text: &str,
+    ) -> PyResult<(&'py PyArray1<i64>, &'py PyArray2<i64>)> {
+        let ret = py.allow_threads(|| textspanrs::get_original_spans(&tokens.0, original_text));
+        Ok(nested_spans_to_numpy(py, ret))
+    }
+
+    /// Batched version of `get_original_spans`, for many documents at once.
+    ///
+    /// The documents are processed in parallel without holding the GIL, and
+    /// the results are returned in the order of the input.
+    ///
+    /// Examples:
+    ///     >>> import textspan
+    ///     >>> textspan.get_original_spans_batch([["foo"], ["a", "b"]], ["FOO", "a b"])
+    ///     [[[(0, 3)]], [[(0, 1)], [(2, 3)]]]
+    ///
+    #[pyfn(m)]
+    #[pyo3(text_signature = "(tokens, original_texts)")]
+    pub fn get_original_spans_batch(
+        py: Python,
+        tokens: Seq<Seq<&str>>,
+        original_texts: Seq<&str>,
+    ) -> PyResult<Vec<Vec<Vec<Span>>>> {
+        let (tokens, original_texts) = (tokens.0, original_texts.0);
+        if tokens.len() != original_texts.len() {
+            return Err(PyValueError::new_err(
+                "`tokens` and `original_texts` must have the same length",
+            ));
+        }
+        Ok(py.allow_threads(|| {
+            textspanrs::get_original_spans_batch(tokens.iter().zip(original_texts))
+        }))
     }
 
     /// Remove overlapping spans from given `spans`.
@@ -88,8 +579,8 @@ fn textspan(_py: Python, m: &PyModule) -> PyResult<()> {
     ///
     #[pyfn(m)]
     #[pyo3(text_signature = "(spans)")]
-    pub fn remove_span_overlaps(_py: Python, spans: Vec<Span>) -> PyResult<Vec<Span>> {
-        Ok(textspanrs::remove_span_overlaps(&spans))
+    pub fn remove_span_overlaps(_py: Python, spans: Spans) -> PyResult<Vec<Span>> {
+        textspanrs::try_remove_span_overlaps(&spans.0).map_err(invalid_span)
     }
 
     /// Remove overlapping spans from given `spans`, and returns remained span indices.
@@ -106,42 +597,71 @@ fn textspan(_py: Python, m: &PyModule) -> PyResult<()> {
     ///
     #[pyfn(m)]
     #[pyo3(text_signature = "(spans)")]
-    pub fn remove_span_overlaps_idx(_py: Python, spans: Vec<Span>) -> PyResult<Vec<usize>> {
-        Ok(textspanrs::remove_span_overlaps_idx(&spans))
+    pub fn remove_span_overlaps_idx(_py: Python, spans: Spans) -> PyResult<Vec<usize>> {
+        textspanrs::try_remove_span_overlaps_idx(&spans.0).map_err(invalid_span)
     }
 
-    fn to_tuple<T>(x: Result<T, T>) -> (T, bool) {
-        match x {
-            Ok(x) => (x, true),
-            Err(x) => (x, false),
-        }
+    /// NumPy version of `remove_span_overlaps_idx`, taking an `(N, 2)` int64
+    /// array of spans.
+    ///
+    /// Examples:
+    ///     >>> import numpy as np
+    ///     >>> import textspan
+    ///     >>> spans = np.array([(0, 2), (0, 3), (2, 4), (5, 7)])
+    ///     >>> spans[textspan.remove_span_overlaps_idx_numpy(spans)].tolist()
+    ///     [[0, 3], [5, 7]]
+    ///
+    #[pyfn(m)]
+    #[pyo3(text_signature = "(spans)")]
+    pub fn remove_span_overlaps_idx_numpy<'py>(
+        py: Python<'py>,
+        spans: PyReadonlyArray2<i64>,
+    ) -> PyResult<&'py PyArray1<i64>> {
+        let spans = spans_from_numpy(spans)?;
+        let ret: Vec<i64> = textspanrs::try_remove_span_overlaps_idx(&spans)
+            .map_err(invalid_span)?
+            .into_iter()
+            .map(|i| i as i64)
+            .collect();
+        Ok(ret.into_pyarray(py))
     }
 
+    /// Converts `span` indices to `target_spans` based indices.
+    /// Expects `target_spans` to be sorted and not overlapping.
+    ///
+    /// Returns the range `[start, end)` of `target_spans` covering `span`,
+    /// as a `LiftedIndex`.
+    ///
     /// Examples:
     ///     >>> import textspan
     ///     >>> spans = [(0, 3), (3, 4), (4, 9), (9, 12)]
-    ///     >>> assert textspan.lift_spans_index((2, 10), spans) == (0, 4)
+    ///     >>> textspan.lift_span_index((2, 9), spans)
+    ///     LiftedIndex(start=0, start_exact=False, end=3, end_exact=True)
     #[pyfn(m)]
     #[pyo3(text_signature = "(span, target_spans)")]
-    pub fn lift_span_index(
-        _py: Python,
-        span: Span,
-        target_spans: Vec<Span>,
-    ) -> PyResult<((usize, bool), (usize, bool))> {
-        let (l, r) = textspanrs::lift_span_index(span, &target_spans);
-        Ok((to_tuple(l), to_tuple(r)))
+    fn lift_span_index(_py: Python, span: PySpan, target_spans: Spans) -> PyResult<LiftedIndex> {
+        textspanrs::try_lift_span_index(span.0, &target_spans.0)
+            .map(LiftedIndex::from_result)
+            .map_err(invalid_span)
     }
 
+    /// Same as `lift_span_index`, for spans sorted by their start.
+    ///
+    /// Examples:
+    ///     >>> import textspan
+    ///     >>> textspan.lift_spans_index([(3, 9)], [(3, 5), (5, 9), (11, 15)])
+    ///     [LiftedIndex(start=0, start_exact=True, end=2, end_exact=True)]
     #[pyfn(m)]
     #[pyo3(text_signature = "(spans, target_spans)")]
-    pub fn lift_spans_index(
+    fn lift_spans_index(
         _py: Python,
-        spans: Vec<Span>,
-        target_spans: Vec<Span>,
-    ) -> PyResult<Vec<((usize, bool), (usize, bool))>> {
-        Ok(textspanrs::lift_spans_index(&spans, &target_spans)
+        spans: Spans,
+        target_spans: Spans,
+    ) -> PyResult<Vec<LiftedIndex>> {
+        Ok(textspanrs::try_lift_spans_index(&spans.0, &target_spans.0)
+            .map_err(invalid_span)?
             .into_iter()
-            .map(|(l, r)| (to_tuple(l), to_tuple(r)))
+            .map(LiftedIndex::from_result)
             .collect())
     }
 
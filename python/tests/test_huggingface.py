@@ -0,0 +1,84 @@
+from types import SimpleNamespace
+
+import pytest
+from textspan.huggingface import (
+    labels_to_spans,
+    merge_windows,
+    normalize_offsets,
+    spans_to_labels,
+)
+
+
+def test_normalize_offsets_encoding():
+    encoding = SimpleNamespace(
+        offsets=[(0, 0), (0, 3), (4, 7), (0, 0)], special_tokens_mask=[1, 0, 0, 1]
+    )
+    assert normalize_offsets(encoding) == [None, (0, 3), (4, 7), None]
+
+
+def test_byte_offsets():
+    text = "東京 タワー"
+    # each char is 3 bytes in utf-8, except for the space
+    offsets = [(0, 6), (7, 10), (11, 13), (13, 16)]
+    # a piece inside a char is widened to the char
+    assert normalize_offsets(offsets, text, byte_offsets=True) == [
+        (0, 2), (3, 4), (4, 5), (5, 6)
+    ]
+    with pytest.raises(ValueError):
+        normalize_offsets(offsets, byte_offsets=True)
+
+
+@pytest.mark.parametrize(
+    "spans, offsets, expected",
+    [
+        ([(0, 5, "X")], [(0, 0), (0, 5), (0, 0)], [None, "B-X", None]),
+        ([(1, 4, "X")], [(0, 2), (2, 3), (3, 6)], ["B-X", "I-X", "I-X"]),
+        ([(3, 4, "E")], [(0, 2), (3, 4), (3, 4), (5, 6)], ["O", "B-E", "I-E", "O"]),
+        ([(4, 6, "B"), (0, 2, "A")], [(0, 2), (2, 4), (4, 6)], ["B-A", "O", "B-B"]),
+        ([(2, 3, "X")], [(0, 2), (3, 5)], ["O", "O"]),
+    ],
+)
+def test_spans_to_labels(spans, offsets, expected):
+    assert spans_to_labels(spans, offsets) == expected
+
+
+def test_spans_to_labels_overlapping_spans():
+    with pytest.raises(ValueError):
+        spans_to_labels([(0, 3, "A"), (2, 4, "B")], [(0, 2), (2, 4)])
+
+
+def test_unsorted_offsets():
+    with pytest.raises(ValueError, match="token offsets must be sorted"):
+        spans_to_labels([(0, 2, "A")], [(5, 6), (0, 2)])
+
+
+@pytest.mark.parametrize(
+    "labels, offsets, expected",
+    [
+        (["B-X", "I-X", "O"], [(0, 1), (2, 3), (4, 5)], [(0, 3, "X")]),
+        (["B-X", "B-X"], [(0, 1), (2, 3)], [(0, 1, "X"), (2, 3, "X")]),
+        (["I-X", "I-Y"], [(0, 1), (2, 3)], [(0, 1, "X"), (2, 3, "Y")]),
+        ([None, "B-X", "I-X", None], [(0, 0), (0, 1), (1, 2), (0, 0)], [(0, 2, "X")]),
+    ],
+)
+def test_labels_to_spans(labels, offsets, expected):
+    assert labels_to_spans(labels, offsets) == expected
+
+
+def test_roundtrip_with_windows():
+    text = "Barack Obama visited Tokyo Tower"
+    tokens = [(0, 6), (7, 12), (13, 20), (21, 26), (27, 32)]
+    spans = [(0, 12, "PER"), (21, 32, "LOC")]
+    # windows of 3 tokens with a stride of 1, each with special tokens
+    windows = [
+        [(0, 0)] + tokens[i : i + 3] + [(0, 0)] for i in range(0, len(tokens) - 1, 2)
+    ]
+    labels = [spans_to_labels(spans, w) for w in windows]
+    assert labels[1] == [None, "O", "B-LOC", "I-LOC", None]
+    offsets, merged = merge_windows(windows, labels)
+    assert offsets == tokens
+    assert labels_to_spans(merged, offsets) == spans
+    assert [text[l:r] for l, r, _ in labels_to_spans(merged, offsets)] == [
+        "Barack Obama",
+        "Tokyo Tower",
+    ]
@@ -1,3 +1,7 @@
+import pickle
+import sys
+
+import numpy as np
 import pytest
 from hypothesis import strategies as st, given
 import textspan
@@ -56,7 +60,195 @@ def test_lift_span_index(span, spans, expected):
     assert textspan.lift_spans_index([span], spans) == [expected]
 
 
+def test_lifted_index():
+    index = textspan.LiftedIndex(1, True, 4, False)
+    assert (index.start, index.start_exact, index.end, index.end_exact) == (1, True, 4, False)
+    (start, _), (end, _) = index
+    assert (start, end) == (1, 4)
+    assert index[1] == index[-1] == (4, False)
+    assert index[:1] == ((1, True),)
+    assert len(index) == 2
+    assert index == textspan.LiftedIndex(1, True, 4, False)
+    assert index != ((1, True), (4, True))
+    assert hash(index) == hash(((1, True), (4, False)))
+    assert pickle.loads(pickle.dumps(index)) == index
+    assert repr(index) == "LiftedIndex(start=1, start_exact=True, end=4, end_exact=False)"
+
+
 def test_remove_span_overlaps():
     spans = [(0, 2), (0, 3), (2, 4), (5, 7)]
     assert textspan.remove_span_overlaps(spans) == [(0, 3), (5, 7)]
     assert textspan.remove_span_overlaps_idx(spans) == [1, 3]
+
+
+def test_align_spans_batch():
+    spans = [[(0, 1), (3, 7)], [(0, 3)]]
+    texts = ["foobarbaz", "foo"]
+    original_texts = ["foo bar baz", "FOO"]
+    assert textspan.align_spans_batch(spans, texts, original_texts) == [
+        textspan.align_spans(*args) for args in zip(spans, texts, original_texts)
+    ]
+    with pytest.raises(ValueError):
+        textspan.align_spans_batch(spans, texts, original_texts[:1])
+
+
+@given(st.lists(st.tuples(st.lists(st.text()), st.text())))
+def test_random_get_original_spans_batch(docs):
+    tokens = [d[0] for d in docs]
+    texts = [d[1] for d in docs]
+    assert textspan.get_original_spans_batch(tokens, texts) == [
+        textspan.get_original_spans(*d) for d in docs
+    ]
+
+
+def test_numpy():
+    spans = [(0, 1), (3, 7)]
+    offsets, ret = textspan.align_spans_numpy(
+        np.array(spans, dtype=np.int64), "foobarbaz", "foo bar baz"
+    )
+    assert ret.dtype == np.int64 and ret.shape == (3, 2)
+    assert [
+        ret[offsets[i] : offsets[i + 1]].tolist() for i in range(len(spans))
+    ] == [[[0, 1]], [[4, 7], [8, 9]]]
+
+    mapping = [[0], [1], [2, 3], [], [5, 7]]
+    mapping_offsets = np.cumsum([0] + [len(x) for x in mapping])
+    mapping_indices = np.array(sum(mapping, []), dtype=np.int64)
+    offsets, ret = textspan.align_spans_by_mapping_numpy(
+        np.array([(0, 2), (2, 5)]), mapping_offsets, mapping_indices
+    )
+    assert offsets.tolist() == [0, 1, 4]
+    assert ret.tolist() == [[0, 2], [2, 4], [5, 6], [7, 8]]
+
+    offsets, ret = textspan.get_original_spans_numpy(["a", "", "b"], "a b")
+    assert offsets.tolist() == [0, 1, 1, 2]
+    assert ret.tolist() == [[0, 1], [2, 3]]
+
+    spans = np.array([(0, 2), (0, 3), (2, 4), (5, 7)])
+    assert textspan.remove_span_overlaps_idx_numpy(spans).tolist() == [1, 3]
+
+
+@pytest.mark.parametrize(
+    "spans", [[[0, 1, 2]], [[-1, 2]]],
+)
+def test_numpy_invalid_spans(spans):
+    with pytest.raises(ValueError):
+        textspan.align_spans_numpy(np.array(spans, dtype=np.int64), "abc", "abc")
+
+
+@pytest.mark.parametrize(
+    "offsets, indices", [([0, 2], [0]), ([1, 1], []), ([0, 2, 1], [0, 1]), ([0, 1], [-1])],
+)
+def test_numpy_invalid_mapping(offsets, indices):
+    with pytest.raises(ValueError):
+        textspan.align_spans_by_mapping_numpy(
+            np.zeros((0, 2), dtype=np.int64),
+            np.array(offsets, dtype=np.int64),
+            np.array(indices, dtype=np.int64),
+        )
+
+
+@given(st.text(), st.text())
+def test_random_alignment(text, original_text):
+    alignment = textspan.Alignment(text, original_text)
+    spans = [(0, len(text))]
+    assert alignment.forward(spans) == textspan.align_spans(spans, text, original_text)
+    assert alignment.backward([(0, len(original_text))]) == textspan.align_spans(
+        [(0, len(original_text))], original_text, text
+    )
+    assert len(alignment.text_to_original) == len(text)
+    assert len(alignment.original_to_text) == len(original_text)
+
+
+def test_alignment_pickle():
+    alignment = textspan.Alignment("foobarbaz", "FOo.BåR baZ")
+    restored = pickle.loads(pickle.dumps(alignment))
+    assert isinstance(restored, textspan.Alignment)
+    assert (restored.text, restored.original_text) == ("foobarbaz", "FOo.BåR baZ")
+    assert restored.text_to_original == alignment.text_to_original
+    assert restored.forward([(0, 9)]) == alignment.forward([(0, 9)])
+
+
+class Str(str):
+    pass
+
+
+@pytest.mark.parametrize(
+    "convert",
+    [list, tuple, iter, lambda xs: (x for x in xs), lambda xs: [Str(x) for x in xs]],
+)
+def test_get_original_spans_input_kinds(convert):
+    tokens = ["foo", "bar"]
+    expected = textspan.get_original_spans(tokens, "FO.o BåR")
+    assert textspan.get_original_spans(convert(tokens), "FO.o BåR") == expected
+    assert textspan.get_original_spans_batch([convert(tokens)], ["FO.o BåR"]) == [
+        expected
+    ]
+
+
+@pytest.mark.parametrize(
+    "convert",
+    [
+        list,
+        tuple,
+        iter,
+        lambda xs: (list(x) for x in xs),
+        lambda xs: tuple(tuple(x) for x in xs),
+    ],
+)
+def test_spans_input_kinds(convert):
+    spans = [(0, 2), (0, 3), (2, 4), (5, 7)]
+    assert textspan.remove_span_overlaps(convert(spans)) == [(0, 3), (5, 7)]
+    assert textspan.align_spans(convert(spans[:1]), "foobarbaz", "foo bar baz") == [
+        [(0, 2)]
+    ]
+    assert textspan.lift_spans_index(convert(spans[:1]), convert(spans[1:2])) == [
+        ((0, True), (1, False))
+    ]
+    alignment = textspan.Alignment("foo", "FOO")
+    assert alignment.forward(convert(spans[:1])) == [[(0, 2)]]
+    mapping = [[0], [1], [2, 3]]
+    assert textspan.align_spans_by_mapping(convert(spans[:1]), convert(mapping)) == [
+        [(0, 2)]
+    ]
+
+
+def test_numpy_missing(monkeypatch):
+    monkeypatch.setitem(sys.modules, "numpy", None)
+    with pytest.raises(ImportError, match="align_spans_numpy requires NumPy"):
+        textspan.align_spans_numpy([[0, 1]], "a", "a")
+
+
+def test_numpy_input_to_list_api():
+    spans = np.array([(0, 2), (0, 3), (2, 4), (5, 7)])
+    assert textspan.remove_span_overlaps(spans) == [(0, 3), (5, 7)]
+    assert textspan.lift_span_index(spans[1], spans[3:]) == ((0, False), (0, False))
+    tokens = np.array(["foo", "bar"])
+    assert textspan.get_original_spans(tokens, "foobar") == [[(0, 3)], [(3, 6)]]
+
+
+@pytest.mark.parametrize("spans", ["abc", [(0, 1, 2)], [5]])
+def test_invalid_spans(spans):
+    with pytest.raises(TypeError):
+        textspan.remove_span_overlaps(spans)
+
+
+@pytest.mark.parametrize(
+    "call",
+    [
+        lambda: textspan.align_spans([(2, 1)], "foo", "FOO"),
+        lambda: textspan.align_spans([(0, 4)], "foo", "FOO"),
+        lambda: textspan.align_spans_batch([[(0, 1)], [(0, 2)]], ["a", "b"], ["a", "b"]),
+        lambda: textspan.align_spans_by_mapping([(0, 3)], [[0], [1]]),
+        lambda: textspan.Alignment("ab", "AB").forward([(0, 3)]),
+        lambda: textspan.Alignment("ab", "AB").backward([(1, 0)]),
+        lambda: textspan.remove_span_overlaps([(0, 1), (3, 2)]),
+        lambda: textspan.remove_span_overlaps_idx([(3, 2)]),
+        lambda: textspan.lift_span_index((1, 0), [(0, 1)]),
+        lambda: textspan.lift_spans_index([(0, 1)], [(1, 0)]),
+    ],
+)
+def test_invalid_span_error(call):
+    with pytest.raises(textspan.InvalidSpanError):
+        call()
+    assert issubclass(textspan.InvalidSpanError, ValueError)
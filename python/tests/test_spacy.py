@@ -0,0 +1,54 @@
+import pytest
+
+spacy = pytest.importorskip("spacy")
+import textspan.spacy as ts  # noqa: E402
+
+
+@pytest.fixture
+def nlp():
+    return spacy.blank("en")
+
+
+@pytest.mark.parametrize(
+    "span, mode, expected",
+    [
+        ((4, 13), "strict", (1, 3)),
+        ((5, 13), "strict", None),
+        ((5, 13), "contract", (2, 3)),
+        ((5, 13), "expand", (1, 3)),
+        ((5, 7), "contract", None),
+        ((3, 4), "expand", None),
+    ],
+)
+def test_token_indices(nlp, span, mode, expected):
+    doc = nlp("New York City")
+    assert ts.token_indices(doc, [span], alignment_mode=mode) == [expected]
+
+
+def test_token_indices_unsorted(nlp):
+    doc = nlp("New York City")
+    assert ts.token_indices(doc, [(9, 13), (0, 3)]) == [(2, 3), (0, 1)]
+    with pytest.raises(ValueError):
+        ts.token_indices(doc, [], alignment_mode="foo")
+
+
+def test_set_ents_removes_overlaps(nlp):
+    doc = nlp("New York City is big")
+    ts.set_ents(doc, [(0, 8, "GPE"), (0, 13, "GPE"), (17, 20, "ADJ")])
+    assert ts.get_spans(doc) == [(0, 13, "GPE"), (17, 20, "ADJ")]
+
+
+def test_set_spans_with_text(nlp):
+    doc = nlp("Tokyo  Tower")
+    ts.set_spans(doc, "sc", [(0, 5, "LOC"), (0, 11, "LOC")], text="tokyo tower")
+    assert ts.get_spans(doc, "sc") == [(0, 5, "LOC"), (0, 12, "LOC")]
+
+
+def test_realign(nlp):
+    doc = nlp("barack obama visited tokyo")
+    ts.set_ents(doc, [(0, 12, "PER"), (21, 26, "LOC")])
+    ts.set_spans(doc, "sc", [(0, 6, "FIRST")])
+    target = nlp("Barack  Obama visited Tokyo!")
+    ts.realign(doc, target, keys=["sc"])
+    assert ts.get_spans(target) == [(0, 13, "PER"), (22, 27, "LOC")]
+    assert ts.get_spans(target, "sc") == [(0, 6, "FIRST")]
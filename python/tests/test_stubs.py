@@ -0,0 +1,45 @@
+"""Checks that `__init__.pyi` matches the compiled module."""
+import ast
+import inspect
+from pathlib import Path
+
+import pytest
+import textspan
+
+STUB = ast.parse((Path(textspan.__file__).parent / "__init__.pyi").read_text())
+
+
+def params(node):
+    args = node.args
+    return [a.arg for a in args.posonlyargs + args.args + args.kwonlyargs]
+
+
+FUNCTIONS = {
+    node.name: node for node in STUB.body if isinstance(node, ast.FunctionDef)
+}
+CLASSES = {node.name: node for node in STUB.body if isinstance(node, ast.ClassDef)}
+
+
+def test_all_names_are_stubbed():
+    assert set(textspan.__all__) == set(FUNCTIONS) | set(CLASSES)
+
+
+@pytest.mark.parametrize("name", sorted(FUNCTIONS))
+def test_function_signature(name):
+    signature = inspect.signature(getattr(textspan, name))
+    assert list(signature.parameters) == params(FUNCTIONS[name])
+
+
+@pytest.mark.parametrize("name", sorted(CLASSES))
+def test_class(name):
+    cls = getattr(textspan, name)
+    for node in CLASSES[name].body:
+        if not isinstance(node, ast.FunctionDef):
+            continue
+        assert hasattr(cls, node.name), node.name
+        if node.name == "__init__":
+            signature = inspect.signature(cls)
+            assert list(signature.parameters) == params(node)[1:]
+        elif not node.name.startswith("__") and callable(getattr(cls, node.name)):
+            signature = inspect.signature(getattr(cls, node.name))
+            assert list(signature.parameters) == params(node)
@@ -1,19 +1,62 @@
+import functools
+
+from textspan import textspan as _textspan
 from textspan.textspan import (
+    Alignment,
+    InvalidSpanError,
+    LiftedIndex,
     get_original_spans,
     align_spans_by_mapping,
     align_spans,
+    align_spans_batch,
+    get_original_spans_batch,
     remove_span_overlaps,
     remove_span_overlaps_idx,
     lift_spans_index,
     lift_span_index,
 )
 
+
+
+def _requires_numpy(f):
+    """Raises `ImportError` if NumPy is missing, instead of the panic of the
+    extension module."""
+
+    @functools.wraps(f)
+    def wrapper(*args, **kwargs):
+        try:
+            import numpy  # noqa: F401
+        except ImportError as e:
+            raise ImportError(
+                f"{f.__name__} requires NumPy: pip install 'pytextspan[numpy]'"
+            ) from e
+        return f(*args, **kwargs)
+
+    return wrapper
+
+
+align_spans_numpy = _requires_numpy(_textspan.align_spans_numpy)
+align_spans_by_mapping_numpy = _requires_numpy(_textspan.align_spans_by_mapping_numpy)
+get_original_spans_numpy = _requires_numpy(_textspan.get_original_spans_numpy)
+remove_span_overlaps_idx_numpy = _requires_numpy(
+    _textspan.remove_span_overlaps_idx_numpy
+)
+
 __all__ = [
+    "Alignment",
+    "InvalidSpanError",
+    "LiftedIndex",
     "get_original_spans",
     "align_spans_by_mapping",
     "align_spans",
+    "align_spans_batch",
+    "get_original_spans_batch",
     "remove_span_overlaps",
     "remove_span_overlaps_idx",
+    "align_spans_numpy",
+    "align_spans_by_mapping_numpy",
+    "get_original_spans_numpy",
+    "remove_span_overlaps_idx_numpy",
     "lift_span_index",
     "lift_spans_index",
 ]
@@ -1,15 +1,122 @@
-from typing import List, Tuple
+from typing import Any, Iterable, Iterator, List, Tuple, overload
+
+# NumPy is an optional dependency, installed with `pytextspan[numpy]`.
+try:
+    import numpy as np
+    import numpy.typing as npt
+
+    _IntArray = npt.NDArray[np.int64]
+except ImportError:
+    _IntArray = Any
+
+Span = Tuple[int, int]
+
+class InvalidSpanError(ValueError):
+    """Raised for a span that is inverted or out of the text it is defined on.
+
+    Examples:
+        >>> import textspan
+        >>> textspan.align_spans([(2, 1)], "foo", "FOO")
+        Traceback (most recent call last):
+        ...
+        textspan.InvalidSpanError: invalid span (2, 1): start is greater than end
+
+    """
+
+class Alignment:
+    """Alignment between `text` and `original_text`.
+
+    The char mapping is computed once, so that spans can be converted
+    repeatedly in both directions without recomputing it.
+
+    Examples:
+        >>> import textspan
+        >>> alignment = textspan.Alignment("foobarbaz", "FOo.BåR baZ")
+        >>> alignment.forward([(0, 3), (3, 6)])
+        [[(0, 3)], [(4, 7)]]
+        >>> alignment.backward([(0, 7)])
+        [[(0, 6)]]
+
+    """
+
+    def __init__(self, text: str, original_text: str) -> None: ...
+    @property
+    def text(self) -> str: ...
+    @property
+    def original_text(self) -> str: ...
+    @property
+    def text_to_original(self) -> List[List[int]]: ...
+    @property
+    def original_to_text(self) -> List[List[int]]: ...
+    def forward(self, spans: Iterable[Span]) -> List[List[Span]]: ...
+    def backward(self, spans: Iterable[Span]) -> List[List[Span]]: ...
+    def __getnewargs__(self) -> Tuple[str, str]: ...
+
+class LiftedIndex:
+    """Index range of `target_spans` covering a span, as returned by
+    `lift_span_index`. It behaves as the nested tuple
+    `((start, start_exact), (end, end_exact))`.
+
+    Examples:
+        >>> import textspan
+        >>> (start, start_exact), (end, end_exact) = textspan.LiftedIndex(1, True, 4, False)
+        >>> textspan.LiftedIndex(1, True, 4, False) == ((1, True), (4, False))
+        True
+
+    """
+
+    def __init__(self, start: int, start_exact: bool, end: int, end_exact: bool) -> None: ...
+    @property
+    def start(self) -> int: ...
+    @property
+    def start_exact(self) -> bool: ...
+    @property
+    def end(self) -> int: ...
+    @property
+    def end_exact(self) -> bool: ...
+    def __len__(self) -> int: ...
+    @overload
+    def __getitem__(self, index: int) -> Tuple[int, bool]: ...
+    @overload
+    def __getitem__(self, index: slice) -> Tuple[Tuple[int, bool], ...]: ...
+    def __iter__(self) -> Iterator[Tuple[int, bool]]: ...
+    def __hash__(self) -> int: ...
+    def __getnewargs__(self) -> Tuple[int, bool, int, bool]: ...
 
 def align_spans(
-    spans: List[Tuple[int, int]], text: str, original_text: str,
-) -> List[List[Tuple[int, int]]]: ...
+    spans: Iterable[Span], text: str, original_text: str
+) -> List[List[Span]]: ...
+def align_spans_batch(
+    spans: Iterable[Iterable[Span]],
+    texts: Iterable[str],
+    original_texts: Iterable[str],
+) -> List[List[List[Span]]]: ...
 def align_spans_by_mapping(
-    spans: List[Tuple[int, int]], mapping: List[List[int]],
-) -> List[List[Tuple[int, int]]]: ...
+    spans: Iterable[Span], mapping: Iterable[Iterable[int]]
+) -> List[List[Span]]: ...
 def get_original_spans(
-    tokens: List[str], original_text: str,
-) -> List[List[Tuple[int, int]]]: ...
-def remove_span_overlaps(tokens: List[Tuple[int, int]]) -> List[Tuple[int, int]]: ...
-def remove_span_overlaps_idx(tokens: List[Tuple[int, int]]) -> List[int]: ...
-def lift_span_index(span: Tuple[int, int], target_spans: List[Tuple[int, int]]) -> Tuple[Tuple[int, bool], Tuple[int, bool]]: ...
-def lift_spans_index(spans: List[Tuple[int, int]], target_spans: List[Tuple[int, int]]) -> List[Tuple[Tuple[int, bool], Tuple[int, bool]]]: ...
+    tokens: Iterable[str], original_text: str
+) -> List[List[Span]]: ...
+def get_original_spans_batch(
+    tokens: Iterable[Iterable[str]], original_texts: Iterable[str]
+) -> List[List[List[Span]]]: ...
+def remove_span_overlaps(spans: Iterable[Span]) -> List[Span]: ...
+def remove_span_overlaps_idx(spans: Iterable[Span]) -> List[int]: ...
+def lift_span_index(span: Span, target_spans: Iterable[Span]) -> LiftedIndex: ...
+def lift_spans_index(
+    spans: Iterable[Span], target_spans: Iterable[Span]
+) -> List[LiftedIndex]: ...
+def align_spans_numpy(
+    spans: _IntArray, text: str, original_text: str
+) -> Tuple[_IntArray, _IntArray]: ...
+def align_spans_by_mapping_numpy(
+    spans: _IntArray,
+    mapping_offsets: _IntArray,
+    mapping_indices: _IntArray,
+) -> Tuple[_IntArray, _IntArray]: ...
+def get_original_spans_numpy(
+    tokens: Iterable[str], original_text: str
+) -> Tuple[_IntArray, _IntArray]: ...
+def remove_span_overlaps_idx_numpy(
+    spans: _IntArray,
+) -> _IntArray: ...
@@ -0,0 +1,202 @@
+"""Helpers for the `offset_mapping` of HuggingFace `tokenizers`.
+
+Functions here accept either a `tokenizers.Encoding` or its list of offsets.
+Special tokens (those in the `special_tokens_mask`, or with empty offsets
+such as `(0, 0)`) get no label, and tokens with overlapping offsets (e.g.
+byte-level BPE pieces of the same char) are labeled as one.
+
+Examples:
+    >>> from textspan.huggingface import spans_to_labels, labels_to_spans
+    >>> offsets = [(0, 0), (0, 6), (7, 10), (10, 12), (13, 18), (0, 0)]
+    >>> labels = spans_to_labels([(7, 12, "PER")], offsets)
+    >>> labels
+    [None, 'O', 'B-PER', 'I-PER', 'O', None]
+    >>> labels_to_spans(labels, offsets)
+    [(7, 12, 'PER')]
+"""
+from bisect import bisect_left, bisect_right
+from typing import Any, List, Optional, Sequence, Tuple
+
+from textspan.textspan import lift_spans_index
+
+Offsets = Sequence[Tuple[int, int]]
+LabeledSpan = Tuple[int, int, str]
+
+
+def _byte_to_char(offsets: Offsets, text: str) -> List[Tuple[int, int]]:
+    starts = [0]
+    for c in text:
+        starts.append(starts[-1] + len(c.encode("utf-8")))
+    # offsets inside a char are widened to the whole char
+    return [(bisect_right(starts, l) - 1, bisect_left(starts, r)) for l, r in offsets]
+
+
+def normalize_offsets(
+    encoding: Any,
+    text: Optional[str] = None,
+    byte_offsets: bool = False,
+) -> List[Optional[Tuple[int, int]]]:
+    """Returns the char offsets of the tokens, with `None` for special tokens.
+
+    Args:
+        encoding: `tokenizers.Encoding`, or a list of `(start, end)` offsets.
+        text: the encoded text. Required if `byte_offsets` is true.
+        byte_offsets: whether the offsets are UTF-8 byte offsets.
+
+    Examples:
+        >>> from textspan.huggingface import normalize_offsets
+        >>> normalize_offsets([(0, 0), (0, 3), (3, 6), (0, 0)], "東京", byte_offsets=True)
+        [None, (0, 1), (1, 2), None]
+    """
+    offsets = [tuple(x) for x in getattr(encoding, "offsets", encoding)]
+    special = getattr(encoding, "special_tokens_mask", None) or [0] * len(offsets)
+    if byte_offsets:
+        if text is None:
+            raise ValueError("`text` is required to convert byte offsets")
+        offsets = _byte_to_char(offsets, text)
+    return [None if s or l >= r else (l, r) for (l, r), s in zip(offsets, special)]
+
+
+def _groups(offsets: Sequence[Optional[Tuple[int, int]]]):
+    """Merges overlapping tokens, returning the merged spans and the group of
+    each token."""
+    spans: List[Tuple[int, int]] = []
+    group: List[Optional[int]] = []
+    for x in offsets:
+        if x is None:
+            group.append(None)
+            continue
+        l, r = x
+        if spans and l < spans[-1][0]:
+            raise ValueError("token offsets must be sorted")
+        elif spans and l < spans[-1][1]:
+            spans[-1] = (spans[-1][0], max(spans[-1][1], r))
+        else:
+            spans.append((l, r))
+        group.append(len(spans) - 1)
+    return spans, group
+
+
+def spans_to_labels(
+    spans: Sequence[LabeledSpan],
+    encoding: Any,
+    text: Optional[str] = None,
+    byte_offsets: bool = False,
+) -> List[Optional[str]]:
+    """Converts the char spans of entities to BIO labels of the tokens.
+
+    Entities not aligned to token boundaries are widened to the tokens they
+    overlap. Special tokens are labeled `None`, to be mapped to e.g. `-100`.
+    For overflowing windows, call this for each window: an entity cut by the
+    start of a window begins with `B-` there.
+
+    Raises:
+        ValueError: if two entities share a token.
+
+    Examples:
+        >>> from textspan.huggingface import spans_to_labels
+        >>> spans_to_labels([(0, 4, "LOC")], [(0, 2), (2, 3), (3, 5)])
+        ['B-LOC', 'I-LOC', 'I-LOC']
+    """
+    offsets = normalize_offsets(encoding, text, byte_offsets)
+    targets, group = _groups(offsets)
+    group_labels: List[str] = ["O"] * len(targets)
+    order = sorted(range(len(spans)), key=lambda i: spans[i][:2])
+    lifted = lift_spans_index([tuple(spans[i][:2]) for i in order], targets)
+    for i, ((start, _), (end, _)) in zip(order, lifted):
+        for j in range(start, end):
+            if group_labels[j] != "O":
+                raise ValueError(f"span {spans[i]} overlaps another span")
+            group_labels[j] = ("B-" if j == start else "I-") + spans[i][2]
+    labels: List[Optional[str]] = []
+    for k, g in enumerate(group):
+        if g is None:
+            labels.append(None)
+        elif k > 0 and group[k - 1] == g and group_labels[g] != "O":
+            # tokens of the same group continue the entity
+            labels.append("I" + group_labels[g][1:])
+        else:
+            labels.append(group_labels[g])
+    return labels
+
+
+def labels_to_spans(
+    labels: Sequence[Optional[str]],
+    encoding: Any,
+    text: Optional[str] = None,
+    byte_offsets: bool = False,
+) -> List[LabeledSpan]:
+    """Converts BIO labels of the tokens to the char spans of entities.
+
+    Labels of special tokens are ignored, and an `I-` label not continuing
+    an entity of the same type begins a new one.
+
+    Examples:
+        >>> from textspan.huggingface import labels_to_spans
+        >>> labels_to_spans(["B-LOC", "I-LOC", "I-PER", "O"], [(0, 2), (3, 5), (5, 6), (7, 9)])
+        [(0, 5, 'LOC'), (5, 6, 'PER')]
+    """
+    offsets = normalize_offsets(encoding, text, byte_offsets)
+    if len(labels) != len(offsets):
+        raise ValueError("`labels` and the offsets must have the same length")
+    ret: List[LabeledSpan] = []
+    current: Optional[List[Any]] = None
+    for label, offset in zip(labels, offsets):
+        if offset is None:
+            continue
+        if label is None or label == "O":
+            current = None
+            continue
+        prefix, _, typ = label.partition("-")
+        if prefix == "I" and current is not None and current[2] == typ:
+            current[1] = max(current[1], offset[1])
+            ret[-1] = tuple(current)  # type: ignore
+            continue
+        current = [offset[0], offset[1], typ]
+        ret.append(tuple(current))  # type: ignore
+    return ret
+
+
+def merge_windows(
+    windows: Sequence[Any],
+    labels: Sequence[Sequence[Optional[str]]],
+    text: Optional[str] = None,
+    byte_offsets: bool = False,
+) -> Tuple[List[Tuple[int, int]], List[str]]:
+    """Merges the labels predicted for overflowing windows of one text.
+
+    Tokens shared by several windows because of the `stride` take the label
+    from the window where they have the most context, i.e. are farthest from
+    the window edges. Returns the offsets and labels of the distinct tokens
+    in text order, ready for `labels_to_spans`.
+
+    Args:
+        windows: encodings or offsets of the windows, e.g.
+            `[encoding] + encoding.overflowing`.
+        labels: the labels of each window.
+
+    Examples:
+        >>> from textspan.huggingface import labels_to_spans, merge_windows
+        >>> windows = [[(0, 1), (2, 3), (4, 5)], [(2, 3), (4, 5), (6, 7)]]
+        >>> labels = [["O", "O", "B-X"], ["O", "B-X", "I-X"]]
+        >>> offsets, merged = merge_windows(windows, labels)
+        >>> merged
+        ['O', 'O', 'B-X', 'I-X']
+        >>> labels_to_spans(merged, offsets)
+        [(4, 7, 'X')]
+    """
+    if len(windows) != len(labels):
+        raise ValueError("`windows` and `labels` must have the same length")
+    best = {}
+    for window, window_labels in zip(windows, labels):
+        offsets = normalize_offsets(window, text, byte_offsets)
+        if len(window_labels) != len(offsets):
+            raise ValueError("labels and offsets of a window must have the same length")
+        positions = [i for i, x in enumerate(offsets) if x is not None]
+        for k, i in enumerate(positions):
+            context = min(k, len(positions) - 1 - k)
+            offset = offsets[i]
+            if offset not in best or context > best[offset][0]:
+                best[offset] = (context, window_labels[i])
+    merged = sorted(best)
+    return merged, [best[x][1] or "O" for x in merged]
@@ -0,0 +1,183 @@
+"""Helpers for spaCy `Doc` and `Span` objects. Requires spaCy v3.
+
+Char spans are `(start, end)` or `(start, end, label)` tuples. They are
+projected onto the tokens of a `Doc` with one of the alignment modes of
+`Doc.char_span`:
+
+- `"strict"`: the span must start and end at token boundaries.
+- `"contract"`: tokens only partially covered are left out.
+- `"expand"`: tokens only partially covered are included.
+
+Examples:
+    >>> import spacy
+    >>> import textspan.spacy
+    >>> doc = spacy.blank("en")("Barack Obama was born in Hawaii")
+    >>> textspan.spacy.set_ents(doc, [(0, 12, "PER"), (25, 31, "LOC")])
+    >>> textspan.spacy.get_spans(doc)
+    [(0, 12, 'PER'), (25, 31, 'LOC')]
+"""
+from typing import List, Optional, Sequence, Tuple, Union
+
+from spacy.tokens import Doc, Span
+
+from textspan.textspan import align_spans, lift_spans_index, remove_span_overlaps_idx
+
+CharSpan = Union[Tuple[int, int], Tuple[int, int, str]]
+ALIGNMENT_MODES = ("strict", "contract", "expand")
+
+
+def _token_spans(doc: Doc) -> List[Tuple[int, int]]:
+    return [(t.idx, t.idx + len(t.text)) for t in doc]
+
+
+def token_indices(
+    doc: Doc, spans: Sequence[CharSpan], alignment_mode: str = "strict"
+) -> List[Optional[Tuple[int, int]]]:
+    """Projects char spans onto the tokens of `doc`, returning the
+    `(start, end)` token indices of each span, or `None` if it cannot be
+    projected.
+
+    Examples:
+        >>> import spacy
+        >>> import textspan.spacy
+        >>> doc = spacy.blank("en")("New York City")
+        >>> textspan.spacy.token_indices(doc, [(0, 8), (1, 8)])
+        [(0, 2), None]
+        >>> textspan.spacy.token_indices(doc, [(1, 8)], alignment_mode="expand")
+        [(0, 2)]
+    """
+    if alignment_mode not in ALIGNMENT_MODES:
+        raise ValueError(f"alignment_mode must be one of {ALIGNMENT_MODES}")
+    targets = _token_spans(doc)
+    order = sorted(range(len(spans)), key=lambda i: tuple(spans[i][:2]))
+    lifted = lift_spans_index([tuple(spans[i][:2]) for i in order], targets)
+    ret: List[Optional[Tuple[int, int]]] = [None] * len(spans)
+    for i, index in zip(order, lifted):
+        l, r = spans[i][:2]
+        start, end = index.start, index.end
+        if alignment_mode == "strict" and not (index.start_exact and index.end_exact):
+            continue
+        if alignment_mode == "contract":
+            # drop the tokens containing the boundaries
+            if not index.start_exact and start < len(targets) and targets[start][0] < l:
+                start += 1
+            if not index.end_exact and end > 0 and targets[end - 1][1] > r:
+                end -= 1
+        if start < end:
+            ret[i] = (start, end)
+    return ret
+
+
+def to_doc_spans(
+    doc: Doc, spans: Sequence[CharSpan], alignment_mode: str = "strict"
+) -> List[Optional[Span]]:
+    """Projects char spans onto `doc` as `Span`s, keeping their labels. Spans
+    that cannot be projected are `None`.
+
+    Examples:
+        >>> import spacy
+        >>> import textspan.spacy
+        >>> doc = spacy.blank("en")("New York City")
+        >>> textspan.spacy.to_doc_spans(doc, [(0, 8, "GPE"), (1, 3)], alignment_mode="contract")
+        [New York, None]
+    """
+    ret: List[Optional[Span]] = []
+    for span, idx in zip(spans, token_indices(doc, spans, alignment_mode)):
+        label = span[2] if len(span) > 2 else ""  # type: ignore
+        ret.append(None if idx is None else Span(doc, idx[0], idx[1], label=label))
+    return ret
+
+
+def _project(
+    doc: Doc, spans: Sequence[CharSpan], text: Optional[str], alignment_mode: str
+) -> List[Span]:
+    if text is not None and text != doc.text:
+        spans = _align(spans, text, doc.text)
+    return [x for x in to_doc_spans(doc, spans, alignment_mode) if x is not None]
+
+
+def _align(spans: Sequence[CharSpan], text: str, target_text: str) -> List[CharSpan]:
+    """Aligns char spans of `text` to `target_text`, covering each span from its
+    first to its last fragment. Spans with no counterpart are dropped."""
+    aligned = align_spans([tuple(x[:2]) for x in spans], text, target_text)
+    ret: List[CharSpan] = []
+    for span, fragments in zip(spans, aligned):
+        if fragments:
+            ret.append((fragments[0][0], fragments[-1][1]) + tuple(span[2:]))  # type: ignore
+    return ret
+
+
+def set_ents(
+    doc: Doc,
+    spans: Sequence[CharSpan],
+    text: Optional[str] = None,
+    alignment_mode: str = "expand",
+) -> None:
+    """Sets `doc.ents` from char spans.
+
+    If `text` is given, the spans are defined in `text` and are first aligned
+    to `doc.text` with `align_spans`. Spans that cannot be projected are
+    skipped, and overlapping spans are removed with `remove_span_overlaps`.
+
+    Examples:
+        >>> import spacy
+        >>> import textspan.spacy
+        >>> doc = spacy.blank("en")("Barack  Obama was born in Hawaii")
+        >>> textspan.spacy.set_ents(doc, [(0, 12, "PER")], text="barack obama was born in hawaii")
+        >>> doc.ents
+        (Barack  Obama,)
+    """
+    ents = _project(doc, spans, text, alignment_mode)
+    keep = remove_span_overlaps_idx([(x.start, x.end) for x in ents])
+    doc.ents = [ents[i] for i in keep]
+
+
+def set_spans(
+    doc: Doc,
+    key: str,
+    spans: Sequence[CharSpan],
+    text: Optional[str] = None,
+    alignment_mode: str = "expand",
+) -> None:
+    """Sets the span group `doc.spans[key]` from char spans, which may
+    overlap. `text` is used as in `set_ents`.
+
+    Examples:
+        >>> import spacy
+        >>> import textspan.spacy
+        >>> doc = spacy.blank("en")("New York City")
+        >>> textspan.spacy.set_spans(doc, "sc", [(0, 8, "GPE"), (0, 13, "GPE")])
+        >>> list(doc.spans["sc"])
+        [New York, New York City]
+    """
+    doc.spans[key] = _project(doc, spans, text, alignment_mode)
+
+
+def get_spans(doc: Doc, key: Optional[str] = None) -> List[Tuple[int, int, str]]:
+    """Returns the char spans of `doc.ents`, or of `doc.spans[key]` if `key`
+    is given, as `(start, end, label)`."""
+    spans = doc.ents if key is None else doc.spans[key]
+    return [(x.start_char, x.end_char, x.label_) for x in spans]
+
+
+def realign(
+    doc: Doc, target: Doc, keys: Sequence[str] = (), alignment_mode: str = "expand"
+) -> None:
+    """Copies the entities and the span groups `keys` of `doc` onto `target`,
+    whose text may differ from `doc.text`, e.g. the original text of a
+    normalized `doc`.
+
+    Examples:
+        >>> import spacy
+        >>> import textspan.spacy
+        >>> nlp = spacy.blank("en")
+        >>> doc = nlp("tokyo tower is tall")
+        >>> textspan.spacy.set_ents(doc, [(0, 11, "LOC")])
+        >>> target = nlp("Tokyo  Tower is tall")
+        >>> textspan.spacy.realign(doc, target)
+        >>> target.ents
+        (Tokyo  Tower,)
+    """
+    set_ents(target, get_spans(doc), doc.text, alignment_mode)
+    for key in keys:
+        set_spans(target, key, get_spans(doc, key), doc.text, alignment_mode)
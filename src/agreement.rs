@@ -0,0 +1,321 @@
+//! Inter-annotator agreement over labeled span annotations of the same text.
+//!
+//! Chance-corrected coefficients are computed at token level, each token being
+//! coded with the label of the first span overlapping it (or no label).
+//! Annotations of slightly different texts can be brought onto a common text
+//! with [`align_annotation`] first.
+use crate::eval::exact_match;
+use crate::{align_spans, LabeledSpan, Span};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::hash::Hash;
+
+/// Projects `spans` of `text` onto `reference_text` with
+/// [`align_spans`](crate::align_spans), covering each span from its first to
+/// its last fragment. Spans with no counterpart are dropped.
+///
+/// # Examples
+///
+/// ```
+/// use textspan::LabeledSpan;
+/// let spans = [LabeledSpan::new(0, 5, "LOC")];
+/// assert_eq!(
+///     textspan::agreement::align_annotation(&spans, "tokyo tower", "Tokyo  Tower"),
+///     [LabeledSpan::new(0, 5, "LOC")]
+/// );
+/// ```
+pub fn align_annotation(
+    spans: &[LabeledSpan],
+    text: &str,
+    reference_text: &str,
+) -> Vec<LabeledSpan> {
+    let raw: Vec<Span> = spans.iter().map(|s| s.span).collect();
+    spans
+        .iter()
+        .zip(align_spans(&raw, text, reference_text))
+        .filter_map(|(s, fragments)| {
+            let start = fragments.first()?.0;
+            let end = fragments.last()?.1;
+            Some(LabeledSpan::new(start, end, s.label.clone()))
+        })
+        .collect()
+}
+
+fn chance_corrected(observed: f64, expected: f64) -> f64 {
+    if expected == 1. {
+        1.
+    } else {
+        (observed - expected) / (1. - expected)
+    }
+}
+
+/// Cohen's kappa of two annotators coding the same items. Returns 1 if both
+/// use a single and same category throughout.
+///
+/// # Panics
+///
+/// Panics if `a` and `b` differ in length.
+///
+/// # Examples
+///
+/// ```
+/// let a = [1, 1, 0, 0, 1];
+/// let b = [1, 0, 0, 0, 1];
+/// assert!((textspan::agreement::cohen_kappa(&a, &b) - 0.6153846).abs() < 1e-6);
+/// ```
+pub fn cohen_kappa<T: Eq + Hash>(a: &[T], b: &[T]) -> f64 {
+    assert_eq!(a.len(), b.len(), "annotators must code the same items");
+    let n = a.len() as f64;
+    if a.is_empty() {
+        return 1.;
+    }
+    let observed = a.iter().zip(b).filter(|(x, y)| x == y).count() as f64 / n;
+    let mut counts: HashMap<&T, (f64, f64)> = HashMap::new();
+    for (x, y) in a.iter().zip(b) {
+        counts.entry(x).or_default().0 += 1.;
+        counts.entry(y).or_default().1 += 1.;
+    }
+    let expected = counts.values().map(|(x, y)| x / n * y / n).sum();
+    chance_corrected(observed, expected)
+}
+
+/// Counts the categories given to each item, for `ratings[annotator][item]`.
+fn item_counts<T: Eq + Hash>(ratings: &[Vec<T>]) -> Vec<HashMap<&T, f64>> {
+    let items = ratings.first().map_or(0, Vec::len);
+    for r in ratings {
+        assert_eq!(r.len(), items, "annotators must code the same items");
+    }
+    (0..items)
+        .map(|i| {
+            let mut counts = HashMap::new();
+            for r in ratings {
+                *counts.entry(&r[i]).or_default() += 1.;
+            }
+            counts
+        })
+        .collect()
+}
+
+/// Fleiss' kappa of any number of annotators, given as
+/// `ratings[annotator][item]`.
+///
+/// # Panics
+///
+/// Panics if annotators code different numbers of items.
+///
+/// # Examples
+///
+/// ```
+/// let ratings = vec![vec![0, 1], vec![0, 0]];
+/// assert!((textspan::agreement::fleiss_kappa(&ratings) + 1. / 3.).abs() < 1e-9);
+/// ```
+pub fn fleiss_kappa<T: Eq + Hash>(ratings: &[Vec<T>]) -> f64 {
+    let counts = item_counts(ratings);
+    let raters = ratings.len() as f64;
+    if counts.is_empty() || raters < 2. {
+        return 1.;
+    }
+    let items = counts.len() as f64;
+    let mut totals: HashMap<&T, f64> = HashMap::new();
+    let mut observed = 0.;
+    for c in &counts {
+        let sq: f64 = c.values().map(|x| x * x).sum();
+        observed += (sq - raters) / (raters * (raters - 1.));
+        for (k, v) in c {
+            *totals.entry(k).or_default() += v;
+        }
+    }
+    observed /= items;
+    let expected = totals
+        .values()
+        .map(|v| (v / (items * raters)).powi(2))
+        .sum();
+    chance_corrected(observed, expected)
+}
+
+/// Krippendorff's alpha for nominal data with no missing values, given as
+/// `ratings[annotator][item]`.
+///
+/// # Panics
+///
+/// Panics if annotators code different numbers of items.
+///
+/// # Examples
+///
+/// ```
+/// let ratings = vec![vec![0, 0, 1, 1], vec![0, 1, 1, 1]];
+/// assert!((textspan::agreement::krippendorff_alpha(&ratings) - 8. / 15.).abs() < 1e-9);
+/// ```
+pub fn krippendorff_alpha<T: Eq + Hash>(ratings: &[Vec<T>]) -> f64 {
+    let counts = item_counts(ratings);
+    let raters = ratings.len() as f64;
+    if counts.is_empty() || raters < 2. {
+        return 1.;
+    }
+    // disagreeing pairable values, and the marginal counts of the values
+    let mut disagreement = 0.;
+    let mut totals: HashMap<&T, f64> = HashMap::new();
+    for c in &counts {
+        let same: f64 = c.values().map(|x| x * (x - 1.)).sum();
+        disagreement += (raters * (raters - 1.) - same) / (raters - 1.);
+        for (k, v) in c {
+            *totals.entry(k).or_default() += v;
+        }
+    }
+    let n: f64 = totals.values().sum();
+    let expected = n * n - totals.values().map(|x| x * x).sum::<f64>();
+    if expected == 0. {
+        return 1.;
+    }
+    1. - (n - 1.) * disagreement / expected
+}
+
+/// Agreement statistics of several annotators.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Agreement {
+    /// Mean of Cohen's kappa over all pairs of annotators, at token level.
+    pub cohen_kappa: f64,
+    /// Fleiss' kappa at token level.
+    pub fleiss_kappa: f64,
+    /// Krippendorff's alpha at token level.
+    pub krippendorff_alpha: f64,
+    /// Mean of the exact-match F1 over all pairs of annotators, at span level.
+    /// Two empty annotations agree perfectly.
+    pub pairwise_f1: f64,
+}
+
+/// Overall and per-label agreement. Per-label statistics code tokens as
+/// having the label or not.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    pub overall: Agreement,
+    pub per_label: BTreeMap<String, Agreement>,
+}
+
+fn token_labels<'a>(spans: &'a [LabeledSpan], tokens: &[Span]) -> Vec<Option<&'a str>> {
+    tokens
+        .iter()
+        .map(|&(l, r)| {
+            spans
+                .iter()
+                .find(|s| s.span.0 < r && l < s.span.1)
+                .map(|s| s.label.as_str())
+        })
+        .collect()
+}
+
+fn statistics<T: Eq + Hash>(ratings: &[Vec<T>], annotations: &[Vec<LabeledSpan>]) -> Agreement {
+    let (mut kappa, mut f1, mut pairs) = (0., 0., 0.);
+    for i in 0..ratings.len() {
+        for j in i + 1..ratings.len() {
+            kappa += cohen_kappa(&ratings[i], &ratings[j]);
+            f1 += if annotations[i].is_empty() && annotations[j].is_empty() {
+                1.
+            } else {
+                exact_match(&annotations[i], &annotations[j]).micro.f1()
+            };
+            pairs += 1.;
+        }
+    }
+    let mean = |x: f64| if pairs == 0. { 1. } else { x / pairs };
+    Agreement {
+        cohen_kappa: mean(kappa),
+        fleiss_kappa: fleiss_kappa(ratings),
+        krippendorff_alpha: krippendorff_alpha(ratings),
+        pairwise_f1: mean(f1),
+    }
+}
+
+/// Computes the agreement of `annotations`, one per annotator, over the same
+/// text tokenized into `tokens`.
+///
+/// # Examples
+///
+/// ```
+/// use textspan::LabeledSpan;
+/// let tokens = [(0, 6), (7, 12), (13, 16), (17, 22)];
+/// let annotations = vec![
+///     vec![LabeledSpan::new(0, 12, "PER"), LabeledSpan::new(17, 22, "LOC")],
+///     vec![LabeledSpan::new(0, 12, "PER"), LabeledSpan::new(17, 22, "ORG")],
+/// ];
+/// let report = textspan::agreement::agreement(&annotations, &tokens);
+/// assert_eq!(report.overall.pairwise_f1, 0.5);
+/// assert_eq!(report.per_label["PER"].cohen_kappa, 1.);
+/// assert!(report.overall.cohen_kappa < 1.);
+/// ```
+pub fn agreement(annotations: &[Vec<LabeledSpan>], tokens: &[Span]) -> Report {
+    let ratings: Vec<_> = annotations
+        .iter()
+        .map(|spans| token_labels(spans, tokens))
+        .collect();
+    let labels: BTreeSet<&str> = annotations
+        .iter()
+        .flatten()
+        .map(|s| s.label.as_str())
+        .collect();
+    let per_label = labels
+        .into_iter()
+        .map(|label| {
+            let binary: Vec<Vec<bool>> = ratings
+                .iter()
+                .map(|r| r.iter().map(|&x| x == Some(label)).collect())
+                .collect();
+            let filtered: Vec<Vec<LabeledSpan>> = annotations
+                .iter()
+                .map(|spans| spans.iter().filter(|s| s.label == label).cloned().collect())
+                .collect();
+            (label.to_string(), statistics(&binary, &filtered))
+        })
+        .collect();
+    Report {
+        overall: statistics(&ratings, annotations),
+        per_label,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cohen_kappa_wikipedia() {
+        // 20 yes/yes, 5 yes/no, 10 no/yes, 15 no/no
+        let mut a = vec![];
+        let mut b = vec![];
+        for &(x, y, n) in &[(1, 1, 20), (1, 0, 5), (0, 1, 10), (0, 0, 15)] {
+            a.extend(vec![x; n]);
+            b.extend(vec![y; n]);
+        }
+        assert!((cohen_kappa(&a, &b) - 0.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn perfect_agreement() {
+        let ratings = vec![vec!["a", "b", "a"]; 3];
+        assert_eq!(fleiss_kappa(&ratings), 1.);
+        assert_eq!(krippendorff_alpha(&ratings), 1.);
+        assert_eq!(cohen_kappa(&ratings[0], &ratings[1]), 1.);
+        let constant = vec![vec!["a", "a"]; 2];
+        assert_eq!(cohen_kappa(&constant[0], &constant[1]), 1.);
+        assert_eq!(krippendorff_alpha(&constant), 1.);
+    }
+
+    #[test]
+    fn two_annotators_alpha_matches_scotts_pi_correction() {
+        // for two annotators, alpha = 1 - (n - 1) / n * (1 - pi)
+        let ratings = vec![vec![0, 0, 1, 1, 2], vec![0, 1, 1, 1, 2]];
+        let alpha = krippendorff_alpha(&ratings);
+        let (po, pe) = (
+            4. / 5.,
+            (3f64 / 10.).powi(2) + (5f64 / 10.).powi(2) + (2f64 / 10.).powi(2),
+        );
+        let pi = (po - pe) / (1. - pe);
+        assert!((alpha - (1. - 9. / 10. * (1. - pi))).abs() < 1e-9);
+        assert!((fleiss_kappa(&ratings) - pi).abs() < 1e-9);
+    }
+
+    #[test]
+    #[should_panic]
+    fn different_lengths() {
+        fleiss_kappa(&[vec![0], vec![0, 1]]);
+    }
+}
@@ -0,0 +1,129 @@
+//! Batch functions aligning many documents in parallel with rayon.
+use crate::{align_spans, get_original_spans, try_align_spans, Error, Span};
+use rayon::prelude::*;
+use std::borrow::Borrow;
+
+/// Batched version of [`align_spans`], taking `(spans, text, original_text)`
+/// for each document.
+///
+/// The documents are aligned in parallel on the current rayon thread pool, and
+/// the results are returned in the order of the input, regardless of the
+/// number of threads.
+///
+/// # Examples
+///
+/// ```
+/// let docs = vec![(vec![(0, 3)], "foo", "FOO"), (vec![(1, 2)], "ab", "a b")];
+/// assert_eq!(
+///     textspan::align_spans_batch(docs),
+///     [vec![vec![(0, 3)]], vec![vec![(2, 3)]]]
+/// );
+/// ```
+pub fn align_spans_batch<I, S, T, U>(docs: I) -> Vec<Vec<Vec<Span>>>
+where
+    I: IntoIterator<Item = (S, T, U)>,
+    S: AsRef<[Span]> + Send,
+    T: AsRef<str> + Send,
+    U: AsRef<str> + Send,
+{
+    let docs: Vec<_> = docs.into_iter().collect();
+    docs.into_par_iter()
+        .map(|(spans, text, original_text)| {
+            align_spans(spans.as_ref(), text.as_ref(), original_text.as_ref())
+        })
+        .collect()
+}
+
+/// Same as [`align_spans_batch`], but returns the error of the first document
+/// with an invalid span, as [`try_align_spans`] does.
+pub fn try_align_spans_batch<I, S, T, U>(docs: I) -> Result<Vec<Vec<Vec<Span>>>, Error>
+where
+    I: IntoIterator<Item = (S, T, U)>,
+    S: AsRef<[Span]> + Send,
+    T: AsRef<str> + Send,
+    U: AsRef<str> + Send,
+{
+    let docs: Vec<_> = docs.into_iter().collect();
+    let ret: Vec<_> = docs
+        .into_par_iter()
+        .map(|(spans, text, original_text)| {
+            try_align_spans(spans.as_ref(), text.as_ref(), original_text.as_ref())
+        })
+        .collect();
+    // collected sequentially so that the error does not depend on scheduling
+    ret.into_iter().collect()
+}
+
+/// Batched version of [`get_original_spans`], taking `(tokens, original_text)`
+/// for each document. The results are in the order of the input.
+///
+/// # Examples
+///
+/// ```
+/// let docs = vec![(vec!["foo"], "FOO"), (vec!["a", "b"], "a b")];
+/// assert_eq!(
+///     textspan::get_original_spans_batch(docs),
+///     [vec![vec![(0, 3)]], vec![vec![(0, 1)], vec![(2, 3)]]]
+/// );
+/// ```
+pub fn get_original_spans_batch<I, T, S, U>(docs: I) -> Vec<Vec<Vec<Span>>>
+where
+    I: IntoIterator<Item = (T, U)>,
+    T: AsRef<[S]> + Send,
+    S: Borrow<str>,
+    U: AsRef<str> + Send,
+{
+    let docs: Vec<_> = docs.into_iter().collect();
+    docs.into_par_iter()
+        .map(|(tokens, original_text)| get_original_spans(tokens.as_ref(), original_text.as_ref()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[quickcheck]
+    fn align_spans_batch_keeps_order(docs: Vec<(Vec<Span>, String, String)>) {
+        let docs: Vec<_> = docs
+            .into_iter()
+            .map(|(spans, text, original_text)| {
+                let len = text.chars().count() + 1;
+                let spans: Vec<_> = spans
+                    .into_iter()
+                    .map(|(l, r)| {
+                        let (l, r) = (l % len, r % len);
+                        (l.min(r), l.max(r))
+                    })
+                    .collect();
+                (spans, text, original_text)
+            })
+            .collect();
+        let expected: Vec<_> = docs
+            .iter()
+            .map(|(spans, text, original_text)| align_spans(spans, text, original_text))
+            .collect();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        assert_eq!(pool.install(|| align_spans_batch(docs.clone())), expected);
+        assert_eq!(try_align_spans_batch(docs), Ok(expected));
+    }
+
+    #[test]
+    fn try_align_spans_batch_returns_first_error() {
+        let docs = vec![
+            (vec![(0, 1)], "a", "a"),
+            (vec![(0, 5)], "ab", "ab"),
+            (vec![(2, 1)], "ab", "ab"),
+        ];
+        assert_eq!(
+            try_align_spans_batch(docs),
+            Err(Error::OutOfBounds {
+                span: (0, 5),
+                len: 2
+            })
+        );
+    }
+}
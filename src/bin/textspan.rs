@@ -0,0 +1,347 @@
+//! Command-line tool for aligning and converting span annotations.
+//!
+//! Annotations are read from stdin and written to stdout as JSONL records (see
+//! [`textspan::formats::jsonl`]). Requires the `cli` feature.
+use clap::{Parser, Subcommand, ValueEnum};
+use serde_json::Value;
+use std::fs;
+use std::io::{self, BufRead, BufWriter, Write};
+use std::process;
+use textspan::formats::jsonl::{read_jsonl, write_jsonl, OffsetUnit, Record, Schema};
+use textspan::formats::{self, brat, conll, spacy, webanno};
+use textspan::{align_spans, remove_span_overlaps_idx, LabeledSpan};
+
+#[derive(Parser)]
+#[command(name = "textspan", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Project the spans of each record onto another text.
+    ///
+    /// The output records have the target text as `text`, and the original
+    /// text in the field the target was read from.
+    Align {
+        /// Read the target text from this file, for every record.
+        #[arg(long)]
+        target: Option<String>,
+        /// Read the target text from this field of each record.
+        #[arg(long, default_value = "target")]
+        target_field: String,
+        #[command(flatten)]
+        io: JsonlArgs,
+    },
+    /// Convert annotations from another format to JSONL, or between JSONL
+    /// schemas and offset units.
+    ///
+    /// The output is always JSONL, in the Prodigy or Doccano schema.
+    Convert {
+        /// Format of the input. `spacy` is a `DocBin` file and `spacy-json`
+        /// the JSON training format of spaCy v2.
+        #[arg(long, value_enum, default_value_t = Format::Jsonl)]
+        from: Format,
+        /// Offset unit of the input, if it is JSONL.
+        #[arg(long, value_enum, default_value_t = Unit::Char)]
+        from_unit: Unit,
+        #[arg(long, value_enum, default_value_t = Output::Prodigy)]
+        to: Output,
+        #[arg(long, value_enum, default_value_t = Unit::Char)]
+        to_unit: Unit,
+        /// The `.txt` file that a brat `.ann` input refers to.
+        #[arg(long, required_if_eq("from", "brat"))]
+        text: Option<String>,
+        /// Span layer of a WebAnno TSV input.
+        #[arg(
+            long,
+            default_value = "de.tudarmstadt.ukp.dkpro.core.api.ner.type.NamedEntity"
+        )]
+        layer: String,
+        /// Feature of the span layer used as the label.
+        #[arg(long, default_value = "value")]
+        feature: String,
+        /// MISC key holding the entity tag of a CoNLL-U input.
+        #[arg(long, default_value = "NE")]
+        tag_key: String,
+    },
+    /// Remove overlapping spans, keeping the longest ones.
+    DedupOverlaps {
+        #[command(flatten)]
+        io: JsonlArgs,
+    },
+    /// Check that every span is valid for its text, and report the invalid
+    /// lines to stderr.
+    Validate {
+        /// Offset unit of the input.
+        #[arg(long, value_enum, default_value_t = Unit::Char)]
+        unit: Unit,
+        /// Also reject records with overlapping spans.
+        #[arg(long)]
+        no_overlaps: bool,
+    },
+}
+
+#[derive(clap::Args)]
+struct JsonlArgs {
+    /// Offset unit of the input and output.
+    #[arg(long, value_enum, default_value_t = Unit::Char)]
+    unit: Unit,
+    /// Schema of the output.
+    #[arg(long, value_enum, default_value_t = Output::Prodigy)]
+    schema: Output,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Unit {
+    Char,
+    Byte,
+    Utf16,
+}
+
+impl From<Unit> for OffsetUnit {
+    fn from(unit: Unit) -> Self {
+        match unit {
+            Unit::Char => OffsetUnit::Char,
+            Unit::Byte => OffsetUnit::Byte,
+            Unit::Utf16 => OffsetUnit::Utf16,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Jsonl,
+    Brat,
+    Conll2003,
+    Conllu,
+    Webanno,
+    Spacy,
+    SpacyJson,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Output {
+    Prodigy,
+    Doccano,
+}
+
+impl From<Output> for Schema {
+    fn from(output: Output) -> Self {
+        match output {
+            Output::Prodigy => Schema::Prodigy,
+            Output::Doccano => Schema::Doccano,
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Projects the spans of `record` onto `target`. Spans split into several
+/// fragments are covered from the first to the last one, and spans with no
+/// counterpart are dropped.
+fn align_record(record: Record, target: String, field: &str) -> Record {
+    let spans: Vec<_> = record.spans.iter().map(|s| s.span).collect();
+    let aligned = align_spans(&spans, &record.text, &target);
+    let spans = record
+        .spans
+        .into_iter()
+        .zip(aligned)
+        .filter_map(|(s, fragments)| {
+            let start = fragments.first()?.0;
+            let end = fragments.last()?.1;
+            Some(LabeledSpan::new(start, end, s.label))
+        })
+        .collect();
+    let mut meta = record.meta;
+    meta.insert(field.to_string(), Value::String(record.text));
+    Record {
+        text: target,
+        spans,
+        meta,
+    }
+}
+
+fn dedup_record(mut record: Record) -> Record {
+    let spans: Vec<_> = record.spans.iter().map(|s| s.span).collect();
+    let keep = remove_span_overlaps_idx(&spans);
+    record.spans = keep.into_iter().map(|i| record.spans[i].clone()).collect();
+    record
+}
+
+fn has_overlaps(record: &Record) -> bool {
+    let spans: Vec<_> = record.spans.iter().map(|s| s.span).collect();
+    remove_span_overlaps_idx(&spans).len() < spans.len()
+}
+
+fn convert(
+    from: Format,
+    from_unit: Unit,
+    text: Option<String>,
+    (layer, feature, tag_key): (&str, &str, &str),
+) -> Result<Vec<Record>> {
+    let stdin = io::stdin();
+    let input = stdin.lock();
+    let record = |text: String, spans: Vec<LabeledSpan>| Record {
+        text,
+        spans,
+        ..Default::default()
+    };
+    Ok(match from {
+        Format::Jsonl => read_jsonl(input, from_unit.into())?,
+        Format::Brat => {
+            let text = fs::read_to_string(text.expect("required by clap"))?;
+            let ann = brat::read_ann(input)?;
+            ann.validate(&text)?;
+            vec![record(text, ann.entities())]
+        }
+        Format::Conll2003 | Format::Conllu => {
+            let docs = if from == Format::Conll2003 {
+                conll::read_conll2003(input)?
+            } else {
+                conll::read_conllu(input, tag_key)?
+            };
+            docs.into_iter()
+                .map(|doc| record(doc.text, doc.entities))
+                .collect()
+        }
+        Format::Webanno => {
+            let doc = webanno::read_tsv3(input)?;
+            let spans = doc.labeled_spans(layer, feature);
+            vec![record(doc.text, spans)]
+        }
+        Format::Spacy | Format::SpacyJson => {
+            let docs = if from == Format::Spacy {
+                spacy::read_docbin(input)?
+            } else {
+                spacy::read_json(input)?
+            };
+            docs.into_iter()
+                .map(|doc| record(doc.text, doc.entities))
+                .collect()
+        }
+    })
+}
+
+/// Validates each line separately, so that every invalid line is reported.
+fn validate(unit: OffsetUnit, no_overlaps: bool) -> Result<bool> {
+    let stdin = io::stdin();
+    let mut ok = true;
+    for (i, line) in stdin.lock().lines().enumerate() {
+        let line = line?;
+        // errors from `read_jsonl` refer to line 1, so only their message
+        // and span are reported
+        let message = match read_jsonl(line.as_bytes(), unit) {
+            Ok(records) if no_overlaps && records.iter().any(has_overlaps) => {
+                "spans overlap".to_string()
+            }
+            Ok(_) => continue,
+            Err(formats::Error::Parse { message, .. }) => message,
+            Err(formats::Error::InvalidSpan { span, message, .. }) => {
+                format!("invalid span {:?}: {}", span, message)
+            }
+            Err(e) => e.to_string(),
+        };
+        eprintln!("line {}: {}", i + 1, message);
+        ok = false;
+    }
+    Ok(ok)
+}
+
+fn run(cli: Cli) -> Result<bool> {
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let stdin = io::stdin();
+    match cli.command {
+        Command::Align {
+            target,
+            target_field,
+            io,
+        } => {
+            let target = target.map(fs::read_to_string).transpose()?;
+            let mut records = vec![];
+            for mut record in read_jsonl(stdin.lock(), io.unit.into())? {
+                let text = match (&target, record.meta.remove(&target_field)) {
+                    (Some(text), _) => text.clone(),
+                    (None, Some(Value::String(text))) => text,
+                    _ => return Err(format!("record without {:?}", target_field).into()),
+                };
+                records.push(align_record(record, text, &target_field));
+            }
+            write_jsonl(&mut out, &records, io.schema.into(), io.unit.into())?;
+        }
+        Command::Convert {
+            from,
+            from_unit,
+            to,
+            to_unit,
+            text,
+            layer,
+            feature,
+            tag_key,
+        } => {
+            let records = convert(from, from_unit, text, (&layer, &feature, &tag_key))?;
+            write_jsonl(&mut out, &records, to.into(), to_unit.into())?;
+        }
+        Command::DedupOverlaps { io } => {
+            let records: Vec<_> = read_jsonl(stdin.lock(), io.unit.into())?
+                .into_iter()
+                .map(dedup_record)
+                .collect();
+            write_jsonl(&mut out, &records, io.schema.into(), io.unit.into())?;
+        }
+        Command::Validate { unit, no_overlaps } => return validate(unit.into(), no_overlaps),
+    }
+    out.flush()?;
+    Ok(true)
+}
+
+fn main() {
+    match run(Cli::parse()) {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("textspan: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn align_record_moves_text_to_field() {
+        let record = Record {
+            text: "foobarbaz".to_string(),
+            spans: vec![LabeledSpan::new(0, 6, "A"), LabeledSpan::new(6, 9, "B")],
+            ..Default::default()
+        };
+        let record = align_record(record, "FOo. BåR".to_string(), "original");
+        assert_eq!(record.text, "FOo. BåR");
+        assert_eq!(record.spans, [LabeledSpan::new(0, 8, "A")]);
+        assert_eq!(record.meta["original"], "foobarbaz");
+    }
+
+    #[test]
+    fn dedup_record_keeps_labels() {
+        let record = Record {
+            text: "abcdefg".to_string(),
+            spans: vec![
+                LabeledSpan::new(0, 2, "A"),
+                LabeledSpan::new(0, 3, "B"),
+                LabeledSpan::new(5, 7, "C"),
+            ],
+            ..Default::default()
+        };
+        assert!(has_overlaps(&record));
+        let record = dedup_record(record);
+        assert_eq!(
+            record.spans,
+            [LabeledSpan::new(0, 3, "B"), LabeledSpan::new(5, 7, "C")]
+        );
+        assert!(!has_overlaps(&record));
+    }
+}
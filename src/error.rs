@@ -0,0 +1,66 @@
+use crate::Span;
+use std::fmt;
+
+/// Error returned by the `try_` functions, e.g. [`try_align_spans`](crate::try_align_spans).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// A span whose start is greater than its end.
+    InvertedSpan { span: Span },
+    /// A span extending past the end of the text, or of the sequence it
+    /// indexes. `len` is the length of it.
+    OutOfBounds { span: Span, len: usize },
+    /// A byte span whose `offset` does not lie on a char boundary.
+    NotCharBoundary { span: Span, offset: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvertedSpan { span } => {
+                write!(f, "invalid span {:?}: start is greater than end", span)
+            }
+            Error::OutOfBounds { span, len } => {
+                write!(
+                    f,
+                    "invalid span {:?}: out of bounds of length {}",
+                    span, len
+                )
+            }
+            Error::NotCharBoundary { span, offset } => write!(
+                f,
+                "invalid span {:?}: byte offset {} is not a char boundary",
+                span, offset
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Checks that `span` is not inverted and, if `len` is given, ends within it.
+pub(crate) fn check_span(span: Span, len: Option<usize>) -> Result<(), Error> {
+    let (l, r) = span;
+    if l > r {
+        return Err(Error::InvertedSpan { span });
+    }
+    match len {
+        Some(len) if r > len => Err(Error::OutOfBounds { span, len }),
+        _ => Ok(()),
+    }
+}
+
+pub(crate) fn check_spans(spans: &[Span], len: Option<usize>) -> Result<(), Error> {
+    spans.iter().try_for_each(|&span| check_span(span, len))
+}
+
+/// Checks that the byte `span` is a valid range of `text`.
+pub(crate) fn check_byte_span(span: Span, text: &str) -> Result<(), Error> {
+    check_span(span, Some(text.len()))?;
+    match [span.0, span.1]
+        .iter()
+        .find(|&&x| !text.is_char_boundary(x))
+    {
+        Some(&offset) => Err(Error::NotCharBoundary { span, offset }),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,388 @@
+//! Evaluation of predicted labeled spans against gold ones, e.g. for named
+//! entity recognition.
+use crate::{LabeledSpan, Span};
+use std::collections::BTreeMap;
+
+fn ratio(x: f64, y: f64) -> f64 {
+    if y == 0. {
+        0.
+    } else {
+        x / y
+    }
+}
+
+fn f1(precision: f64, recall: f64) -> f64 {
+    ratio(2. * precision * recall, precision + recall)
+}
+
+fn overlaps((l, r): Span, (l2, r2): Span) -> bool {
+    l < r2 && l2 < r
+}
+
+/// True positive, false positive and false negative counts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub false_negatives: usize,
+}
+
+impl Counts {
+    pub fn precision(&self) -> f64 {
+        let tp = self.true_positives as f64;
+        ratio(tp, tp + self.false_positives as f64)
+    }
+
+    pub fn recall(&self) -> f64 {
+        let tp = self.true_positives as f64;
+        ratio(tp, tp + self.false_negatives as f64)
+    }
+
+    pub fn f1(&self) -> f64 {
+        f1(self.precision(), self.recall())
+    }
+}
+
+/// Micro-averaged and per-label counts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Scores {
+    pub micro: Counts,
+    pub per_label: BTreeMap<String, Counts>,
+}
+
+impl Scores {
+    /// Returns the mean of the F1 scores of the labels.
+    pub fn macro_f1(&self) -> f64 {
+        let sum: f64 = self.per_label.values().map(Counts::f1).sum();
+        ratio(sum, self.per_label.len() as f64)
+    }
+
+    fn add(&mut self, label: &str, f: impl Fn(&mut Counts)) {
+        f(&mut self.micro);
+        f(self.per_label.entry(label.to_string()).or_default());
+    }
+}
+
+/// Counts a predicted span as correct if its span and label are the same as
+/// those of a gold span. Each gold span matches at most one prediction.
+///
+/// # Examples
+///
+/// ```
+/// use textspan::LabeledSpan;
+/// let gold = [LabeledSpan::new(0, 5, "PER"), LabeledSpan::new(10, 15, "LOC")];
+/// let pred = [LabeledSpan::new(0, 5, "PER"), LabeledSpan::new(10, 14, "LOC")];
+/// let scores = textspan::eval::exact_match(&gold, &pred);
+/// assert_eq!(scores.micro.f1(), 0.5);
+/// assert_eq!(scores.per_label["PER"].f1(), 1.);
+/// ```
+pub fn exact_match(gold: &[LabeledSpan], pred: &[LabeledSpan]) -> Scores {
+    match_spans(gold, pred, |g, p| g == p)
+}
+
+/// Counts a predicted span as correct if it overlaps a gold span with the same
+/// label. Each gold span matches at most one prediction, in order.
+///
+/// # Examples
+///
+/// ```
+/// use textspan::LabeledSpan;
+/// let gold = [LabeledSpan::new(0, 5, "PER"), LabeledSpan::new(10, 15, "LOC")];
+/// let pred = [LabeledSpan::new(0, 5, "PER"), LabeledSpan::new(10, 14, "LOC")];
+/// assert_eq!(textspan::eval::partial_match(&gold, &pred).micro.f1(), 1.);
+/// ```
+pub fn partial_match(gold: &[LabeledSpan], pred: &[LabeledSpan]) -> Scores {
+    match_spans(gold, pred, |g, p| {
+        g.label == p.label && overlaps(g.span, p.span)
+    })
+}
+
+fn match_spans(
+    gold: &[LabeledSpan],
+    pred: &[LabeledSpan],
+    matches: impl Fn(&LabeledSpan, &LabeledSpan) -> bool,
+) -> Scores {
+    let mut scores = Scores::default();
+    let mut used = vec![false; gold.len()];
+    for p in pred {
+        let found = (0..gold.len()).find(|&i| !used[i] && matches(&gold[i], p));
+        match found {
+            Some(i) => {
+                used[i] = true;
+                scores.add(&p.label, |c| c.true_positives += 1);
+            }
+            None => scores.add(&p.label, |c| c.false_positives += 1),
+        }
+    }
+    for (g, used) in gold.iter().zip(used) {
+        if !used {
+            scores.add(&g.label, |c| c.false_negatives += 1);
+        }
+    }
+    scores
+}
+
+/// Labels each token with the first span overlapping it, and counts the
+/// tokens whose labels agree.
+///
+/// # Examples
+///
+/// ```
+/// use textspan::LabeledSpan;
+/// let tokens = [(0, 6), (7, 12), (13, 16)];
+/// let gold = [LabeledSpan::new(0, 12, "PER")];
+/// let pred = [LabeledSpan::new(7, 16, "PER")];
+/// let scores = textspan::eval::token_level(&gold, &pred, &tokens);
+/// assert_eq!(scores.micro.precision(), 0.5);
+/// assert_eq!(scores.micro.recall(), 0.5);
+/// ```
+pub fn token_level(gold: &[LabeledSpan], pred: &[LabeledSpan], tokens: &[Span]) -> Scores {
+    let label = |spans: &'_ [LabeledSpan], token: Span| {
+        spans
+            .iter()
+            .find(|s| overlaps(s.span, token))
+            .map(|s| s.label.clone())
+    };
+    let mut scores = Scores::default();
+    for &token in tokens {
+        match (label(gold, token), label(pred, token)) {
+            (Some(g), Some(p)) if g == p => scores.add(&g, |c| c.true_positives += 1),
+            (g, p) => {
+                if let Some(p) = p {
+                    scores.add(&p, |c| c.false_positives += 1);
+                }
+                if let Some(g) = g {
+                    scores.add(&g, |c| c.false_negatives += 1);
+                }
+            }
+        }
+    }
+    scores
+}
+
+/// The categories of SemEval-2013 Task 9.1 (Segura-Bedmar et al., 2013).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Categories {
+    pub correct: usize,
+    pub incorrect: usize,
+    pub partial: usize,
+    pub missing: usize,
+    pub spurious: usize,
+}
+
+impl Categories {
+    /// Number of gold spans.
+    pub fn possible(&self) -> usize {
+        self.correct + self.incorrect + self.partial + self.missing
+    }
+
+    /// Number of predicted spans.
+    pub fn actual(&self) -> usize {
+        self.correct + self.incorrect + self.partial + self.spurious
+    }
+
+    /// Partial matches count as half correct.
+    pub fn precision(&self) -> f64 {
+        ratio(
+            self.correct as f64 + 0.5 * self.partial as f64,
+            self.actual() as f64,
+        )
+    }
+
+    /// Partial matches count as half correct.
+    pub fn recall(&self) -> f64 {
+        ratio(
+            self.correct as f64 + 0.5 * self.partial as f64,
+            self.possible() as f64,
+        )
+    }
+
+    pub fn f1(&self) -> f64 {
+        f1(self.precision(), self.recall())
+    }
+}
+
+/// [`Categories`] under the four schemas of SemEval-2013.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SemEvalScores {
+    /// Exact span and same label.
+    pub strict: Categories,
+    /// Exact span, regardless of the label.
+    pub exact: Categories,
+    /// Overlapping span, regardless of the label. Only this schema has
+    /// partial matches.
+    pub partial: Categories,
+    /// Overlapping span and same label.
+    pub ent_type: Categories,
+}
+
+/// Categorizes each predicted span by its best matching gold span, as in
+/// SemEval-2013 Task 9.1: a gold span with the same span, or else the first
+/// overlapping one. Each gold span matches one prediction at most, so further
+/// predictions of it are spurious. Gold spans that match no prediction are
+/// missing.
+///
+/// # Examples
+///
+/// ```
+/// use textspan::LabeledSpan;
+/// let gold = [LabeledSpan::new(0, 5, "PER"), LabeledSpan::new(10, 15, "LOC")];
+/// let pred = [
+///     LabeledSpan::new(0, 5, "ORG"),
+///     LabeledSpan::new(10, 14, "LOC"),
+///     LabeledSpan::new(20, 25, "LOC"),
+/// ];
+/// let scores = textspan::eval::semeval(&gold, &pred);
+/// assert_eq!(scores.strict.incorrect, 2);
+/// assert_eq!(scores.strict.spurious, 1);
+/// assert_eq!(scores.exact.correct, 1);
+/// assert_eq!(scores.partial.partial, 1);
+/// assert_eq!(scores.ent_type.correct, 1);
+/// ```
+pub fn semeval(gold: &[LabeledSpan], pred: &[LabeledSpan]) -> SemEvalScores {
+    let mut scores = SemEvalScores::default();
+    let mut used = vec![false; gold.len()];
+    for p in pred {
+        // a gold span is matched by one prediction at most
+        let unused = |i: &usize| !used[*i];
+        let found = (0..gold.len())
+            .filter(unused)
+            .find(|&i| gold[i].span == p.span)
+            .or_else(|| {
+                (0..gold.len())
+                    .filter(unused)
+                    .find(|&i| overlaps(gold[i].span, p.span))
+            });
+        let g = match found {
+            Some(i) => {
+                used[i] = true;
+                &gold[i]
+            }
+            None => {
+                scores.strict.spurious += 1;
+                scores.exact.spurious += 1;
+                scores.partial.spurious += 1;
+                scores.ent_type.spurious += 1;
+                continue;
+            }
+        };
+        let same_span = g.span == p.span;
+        let same_label = g.label == p.label;
+        let hit = |c: &mut Categories, ok: bool| {
+            if ok {
+                c.correct += 1
+            } else {
+                c.incorrect += 1
+            }
+        };
+        hit(&mut scores.strict, same_span && same_label);
+        hit(&mut scores.exact, same_span);
+        hit(&mut scores.ent_type, same_label);
+        if same_span {
+            scores.partial.correct += 1;
+        } else {
+            scores.partial.partial += 1;
+        }
+    }
+    let missing = used.iter().filter(|&&u| !u).count();
+    scores.strict.missing += missing;
+    scores.exact.missing += missing;
+    scores.partial.missing += missing;
+    scores.ent_type.missing += missing;
+    scores
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(spans: &[(usize, usize, &str)]) -> Vec<LabeledSpan> {
+        spans
+            .iter()
+            .map(|&(l, r, label)| LabeledSpan::new(l, r, label))
+            .collect()
+    }
+
+    #[test]
+    fn exact_match_handmade() {
+        let gold = spans(&[(0, 2, "A"), (0, 2, "A"), (3, 5, "B")]);
+        let pred = spans(&[(0, 2, "A"), (3, 5, "A"), (6, 7, "C")]);
+        let scores = exact_match(&gold, &pred);
+        assert_eq!(
+            scores.micro,
+            Counts {
+                true_positives: 1,
+                false_positives: 2,
+                false_negatives: 2
+            }
+        );
+        assert_eq!(scores.per_label["A"].precision(), 0.5);
+        assert_eq!(scores.per_label["A"].recall(), 0.5);
+        assert_eq!(scores.per_label["B"].f1(), 0.);
+        assert_eq!(scores.per_label["C"].recall(), 0.);
+        assert_eq!(scores.macro_f1(), 0.5 / 3.);
+        assert_eq!(exact_match(&[], &[]).micro.f1(), 0.);
+    }
+
+    #[test]
+    fn partial_match_is_one_to_one() {
+        let gold = spans(&[(0, 4, "A")]);
+        let pred = spans(&[(0, 2, "A"), (2, 4, "A")]);
+        let scores = partial_match(&gold, &pred);
+        assert_eq!(scores.micro.true_positives, 1);
+        assert_eq!(scores.micro.false_positives, 1);
+    }
+
+    #[test]
+    fn token_level_handmade() {
+        let tokens = [(0, 1), (1, 2), (2, 3), (3, 4)];
+        let gold = spans(&[(0, 2, "A"), (3, 4, "B")]);
+        let pred = spans(&[(1, 3, "A"), (3, 4, "A")]);
+        let scores = token_level(&gold, &pred, &tokens);
+        assert_eq!(
+            scores.per_label["A"],
+            Counts {
+                true_positives: 1,
+                false_positives: 2,
+                false_negatives: 1
+            }
+        );
+        assert_eq!(scores.per_label["B"].false_negatives, 1);
+    }
+
+    #[test]
+    fn semeval_handmade() {
+        let gold = spans(&[(0, 3, "A"), (5, 8, "B"), (10, 12, "A")]);
+        let pred = spans(&[(0, 3, "A"), (5, 7, "A")]);
+        let scores = semeval(&gold, &pred);
+        let c = |correct, incorrect, partial| Categories {
+            correct,
+            incorrect,
+            partial,
+            missing: 1,
+            spurious: 0,
+        };
+        assert_eq!(scores.strict, c(1, 1, 0));
+        assert_eq!(scores.exact, c(1, 1, 0));
+        assert_eq!(scores.partial, c(1, 0, 1));
+        assert_eq!(scores.ent_type, c(1, 1, 0));
+        assert_eq!(scores.partial.precision(), 0.75);
+        assert_eq!(scores.partial.recall(), 0.5);
+    }
+
+    #[test]
+    fn semeval_gold_span_matched_once() {
+        let gold = spans(&[(0, 4, "A")]);
+        let scores = semeval(&gold, &spans(&[(0, 4, "A"), (0, 4, "A")]));
+        assert_eq!(scores.strict.correct, 1);
+        assert_eq!(scores.strict.spurious, 1);
+        assert_eq!(scores.strict.possible(), 1);
+        assert_eq!(scores.strict.recall(), 1.0);
+        assert_eq!(scores.strict.precision(), 0.5);
+
+        let scores = semeval(&gold, &spans(&[(0, 2, "A"), (2, 4, "A")]));
+        assert_eq!(scores.partial.partial, 1);
+        assert_eq!(scores.partial.spurious, 1);
+        assert_eq!(scores.partial.missing, 0);
+    }
+}
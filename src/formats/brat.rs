@@ -0,0 +1,364 @@
+//! brat standoff format (`.ann` files).
+//!
+//! Offsets in `.ann` files are char offsets into the accompanying `.txt` file,
+//! so they can be used with the rest of this crate as they are.
+use super::{Error, Result};
+use crate::{LabeledSpan, Span};
+use std::io::{BufRead, BufReader, Read, Write};
+
+/// A text-bound annotation (`T1\tORG 0 5;8 12\tFoo Bar`), possibly made of
+/// several discontinuous fragments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextBound {
+    pub id: String,
+    pub label: String,
+    pub spans: Vec<Span>,
+    /// The covered text, fragments joined with a space.
+    pub text: String,
+}
+
+impl TextBound {
+    /// Creates a text-bound annotation of the `spans` in `text`, e.g. one
+    /// element of the output of [`align_spans`](crate::align_spans).
+    ///
+    /// Returns [`Error::InvalidSpan`] if a span is inverted or out of `text`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use textspan::formats::brat::TextBound;
+    /// let spans = textspan::align_spans(&[(0, 6)], "foobar", "foo. bar");
+    /// let t = TextBound::new("T1", "X", spans[0].clone(), "foo. bar").unwrap();
+    /// assert_eq!(t.spans, [(0, 3), (5, 8)]);
+    /// assert_eq!(t.text, "foo bar");
+    /// assert!(TextBound::new("T2", "X", vec![(5, 3)], "foo. bar").is_err());
+    /// ```
+    pub fn new(
+        id: impl Into<String>,
+        label: impl Into<String>,
+        spans: Vec<Span>,
+        text: &str,
+    ) -> Result<Self> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut fragments = vec![];
+        for &(l, r) in &spans {
+            if l > r || r > chars.len() {
+                return Err(Error::InvalidSpan {
+                    line: None,
+                    span: (l, r),
+                    message: format!("out of the text of length {}", chars.len()),
+                });
+            }
+            fragments.push(chars[l..r].iter().collect::<String>());
+        }
+        Ok(TextBound {
+            id: id.into(),
+            label: label.into(),
+            spans,
+            text: fragments.join(" "),
+        })
+    }
+}
+
+/// An attribute (`A1\tNegated T1`) or multi-valued attribute
+/// (`A2\tConfidence T1 High`). Legacy modifiers (`M1`) are read as attributes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attribute {
+    pub id: String,
+    pub name: String,
+    pub target: String,
+    pub value: Option<String>,
+}
+
+/// A relation (`R1\tOrigin Arg1:T3 Arg2:T4`), with its `(role, id)` arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relation {
+    pub id: String,
+    pub label: String,
+    pub args: Vec<(String, String)>,
+}
+
+/// A note (`#1\tAnnotatorNotes T1\tsome text`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Note {
+    pub id: String,
+    pub kind: String,
+    pub target: String,
+    pub text: String,
+}
+
+/// The annotations of one `.ann` file.
+///
+/// Lines of other kinds (events, normalizations, equivalences) are kept
+/// verbatim in `others` so that they survive a round trip.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Annotations {
+    pub text_bounds: Vec<TextBound>,
+    pub attributes: Vec<Attribute>,
+    pub relations: Vec<Relation>,
+    pub notes: Vec<Note>,
+    pub others: Vec<String>,
+}
+
+impl Annotations {
+    /// Returns the text-bound annotations as labeled spans, covering each
+    /// annotation from its first to its last fragment.
+    pub fn entities(&self) -> Vec<LabeledSpan> {
+        self.text_bounds
+            .iter()
+            .filter_map(|t| {
+                let start = t.spans.first()?.0;
+                let end = t.spans.last()?.1;
+                Some(LabeledSpan::new(start, end, t.label.clone()))
+            })
+            .collect()
+    }
+
+    /// Checks that every fragment lies within `text` and that the text stored
+    /// in each text-bound annotation matches `text`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let ann = "T1\tPER 0 3\tBob\nT2\tLOC 8 13\tParis\n";
+    /// let ann = textspan::formats::brat::read_ann(ann.as_bytes()).unwrap();
+    /// assert!(ann.validate("Bob saw Paris").is_ok());
+    /// assert!(ann.validate("Bob saw Rome").is_err());
+    /// ```
+    pub fn validate(&self, text: &str) -> Result<()> {
+        let len = text.chars().count();
+        for t in &self.text_bounds {
+            if t.spans.is_empty() {
+                return Err(Error::Parse {
+                    line: None,
+                    message: format!("{} has no fragment", t.id),
+                });
+            }
+            for &(l, r) in &t.spans {
+                if l > r || r > len {
+                    return Err(Error::InvalidSpan {
+                        line: None,
+                        span: (l, r),
+                        message: format!("{} is out of the text of length {}", t.id, len),
+                    });
+                }
+            }
+            let expected = TextBound::new("", "", t.spans.clone(), text)?.text;
+            if expected != t.text {
+                return Err(Error::InvalidSpan {
+                    line: None,
+                    span: t.spans[0],
+                    message: format!(
+                        "{} has text {:?}, but the document has {:?}",
+                        t.id, t.text, expected
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_offset(s: &str, line: usize) -> Result<usize> {
+    s.parse().map_err(|_| Error::Parse {
+        line: Some(line),
+        message: format!("invalid offset {:?}", s),
+    })
+}
+
+/// Reads a brat `.ann` file.
+///
+/// # Examples
+///
+/// ```
+/// let ann = "T1\tORG 0 5;8 12\tSouth Africa\nR1\tIn Arg1:T1 Arg2:T2\n";
+/// let ann = textspan::formats::brat::read_ann(ann.as_bytes()).unwrap();
+/// assert_eq!(ann.text_bounds[0].spans, [(0, 5), (8, 12)]);
+/// assert_eq!(ann.relations[0].args[1], ("Arg2".to_string(), "T2".to_string()));
+/// ```
+pub fn read_ann<R: Read>(reader: R) -> Result<Annotations> {
+    let mut ann = Annotations::default();
+    for (i, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        let lineno = i + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let err = |message: &str| Error::Parse {
+            line: Some(lineno),
+            message: message.to_string(),
+        };
+        let mut fields = line.splitn(3, '\t');
+        let id = fields.next().unwrap_or_default().to_string();
+        let body = fields.next().ok_or_else(|| err("missing tab after id"))?;
+        let rest = fields.next();
+        match id.chars().next() {
+            Some('T') => {
+                let (label, offsets) =
+                    body.split_once(' ').ok_or_else(|| err("missing offsets"))?;
+                let spans = offsets
+                    .split(';')
+                    .map(|frag| {
+                        let (l, r) = frag
+                            .trim()
+                            .split_once(' ')
+                            .ok_or_else(|| err("invalid fragment"))?;
+                        Ok((parse_offset(l, lineno)?, parse_offset(r, lineno)?))
+                    })
+                    .collect::<Result<_>>()?;
+                ann.text_bounds.push(TextBound {
+                    id,
+                    label: label.to_string(),
+                    spans,
+                    text: rest.unwrap_or_default().to_string(),
+                });
+            }
+            Some('A') | Some('M') => {
+                let mut parts = body.split(' ');
+                let name = parts.next().unwrap_or_default().to_string();
+                let target = parts
+                    .next()
+                    .ok_or_else(|| err("missing attribute target"))?
+                    .to_string();
+                ann.attributes.push(Attribute {
+                    id,
+                    name,
+                    target,
+                    value: parts.next().map(|s| s.to_string()),
+                });
+            }
+            Some('R') => {
+                let mut parts = body.split(' ').filter(|s| !s.is_empty());
+                let label = parts.next().unwrap_or_default().to_string();
+                let args = parts
+                    .map(|arg| {
+                        let (role, target) = arg
+                            .split_once(':')
+                            .ok_or_else(|| err("invalid relation argument"))?;
+                        Ok((role.to_string(), target.to_string()))
+                    })
+                    .collect::<Result<_>>()?;
+                ann.relations.push(Relation { id, label, args });
+            }
+            Some('#') => {
+                let (kind, target) = body
+                    .split_once(' ')
+                    .ok_or_else(|| err("missing note target"))?;
+                ann.notes.push(Note {
+                    id,
+                    kind: kind.to_string(),
+                    target: target.to_string(),
+                    text: rest.unwrap_or_default().to_string(),
+                });
+            }
+            _ => ann.others.push(line),
+        }
+    }
+    Ok(ann)
+}
+
+/// Writes `ann` in the brat `.ann` format.
+pub fn write_ann<W: Write>(mut writer: W, ann: &Annotations) -> Result<()> {
+    for t in &ann.text_bounds {
+        let offsets: Vec<_> = t
+            .spans
+            .iter()
+            .map(|(l, r)| format!("{} {}", l, r))
+            .collect();
+        writeln!(
+            writer,
+            "{}\t{} {}\t{}",
+            t.id,
+            t.label,
+            offsets.join(";"),
+            t.text
+        )?;
+    }
+    for a in &ann.attributes {
+        match &a.value {
+            Some(value) => writeln!(writer, "{}\t{} {} {}", a.id, a.name, a.target, value)?,
+            None => writeln!(writer, "{}\t{} {}", a.id, a.name, a.target)?,
+        }
+    }
+    for r in &ann.relations {
+        let args: Vec<_> = r
+            .args
+            .iter()
+            .map(|(role, target)| format!("{}:{}", role, target))
+            .collect();
+        writeln!(writer, "{}\t{} {}", r.id, r.label, args.join(" "))?;
+    }
+    for n in &ann.notes {
+        writeln!(writer, "{}\t{} {}\t{}", n.id, n.kind, n.target, n.text)?;
+    }
+    for line in &ann.others {
+        writeln!(writer, "{}", line)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANN: &str = "T1\tOrganization 0 4\tSony\n\
+                       T2\tCountry 8 13;14 19\tSouth Korea\n\
+                       A1\tNegated T1\n\
+                       A2\tConfidence T2 High\n\
+                       R1\tOrigin Arg1:T1 Arg2:T2\n\
+                       #1\tAnnotatorNotes T1\tA company\twith a tab\n\
+                       E1\tMerge-Org:T1 Org1:T2\n";
+
+    #[test]
+    fn read_ann_handmade() {
+        let ann = read_ann(ANN.as_bytes()).unwrap();
+        assert_eq!(ann.text_bounds.len(), 2);
+        assert_eq!(ann.text_bounds[1].spans, [(8, 13), (14, 19)]);
+        assert_eq!(ann.text_bounds[1].text, "South Korea");
+        assert_eq!(ann.attributes[0].value, None);
+        assert_eq!(ann.attributes[1].value.as_deref(), Some("High"));
+        assert_eq!(ann.relations[0].label, "Origin");
+        assert_eq!(ann.notes[0].text, "A company\twith a tab");
+        assert_eq!(ann.others, ["E1\tMerge-Org:T1 Org1:T2"]);
+        assert_eq!(
+            ann.entities(),
+            [
+                LabeledSpan::new(0, 4, "Organization"),
+                LabeledSpan::new(8, 19, "Country")
+            ]
+        );
+        ann.validate("Sony in South\nKorea").unwrap();
+    }
+
+    #[test]
+    fn ann_roundtrip() {
+        let ann = read_ann(ANN.as_bytes()).unwrap();
+        let mut buf = vec![];
+        write_ann(&mut buf, &ann).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), ANN);
+    }
+
+    #[test]
+    fn read_ann_errors() {
+        for (input, line) in [
+            ("T1\tX 0 3\tfoo\nT2\tX 1\tb\n", 2),
+            ("T1\tX a 3\tfoo\n", 1),
+            ("R1\tRel Arg1\n", 1),
+            ("T1 X 0 3\n", 1),
+        ] {
+            match read_ann(input.as_bytes()) {
+                Err(Error::Parse { line: l, .. }) => assert_eq!(l, Some(line), "{}", input),
+                ret => panic!("{:?}", ret),
+            }
+        }
+    }
+
+    #[test]
+    fn validate_out_of_range() {
+        let ann = read_ann("T1\tX 2 10\tfoo\n".as_bytes()).unwrap();
+        assert!(matches!(
+            ann.validate("foo"),
+            Err(Error::InvalidSpan { span: (2, 10), .. })
+        ));
+    }
+}
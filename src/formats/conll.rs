@@ -0,0 +1,348 @@
+//! CoNLL-2003 and CoNLL-U readers that rebuild the document text.
+//!
+//! CoNLL files only keep tokens, so the text is reconstructed from them:
+//! tokens are separated by a space (unless CoNLL-U says `SpaceAfter=No`), and
+//! sentences by a newline. The char spans of the tokens in the rebuilt text
+//! can then be projected onto the raw documents with
+//! [`align_spans`](crate::align_spans) or
+//! [`get_original_spans`](crate::get_original_spans).
+use super::{decode_tags, Error, Result};
+use crate::{get_original_spans, LabeledSpan, Span};
+use std::io::{BufRead, BufReader, Read};
+
+/// A token with its span in [`Document::text`] and all the columns of its
+/// line, including the form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub span: Span,
+    pub form: String,
+    pub columns: Vec<String>,
+}
+
+/// A document rebuilt from a CoNLL file.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Document {
+    pub text: String,
+    pub tokens: Vec<Token>,
+    /// Spans of the sentences in `text`.
+    pub sentences: Vec<Span>,
+    pub entities: Vec<LabeledSpan>,
+}
+
+/// A token line being read, before its span is known.
+struct Word {
+    form: String,
+    columns: Vec<String>,
+    tag: String,
+    space_after: bool,
+    /// 1-based line number, for errors.
+    line: usize,
+}
+
+impl Document {
+    pub fn token_spans(&self) -> Vec<Span> {
+        self.tokens.iter().map(|t| t.span).collect()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Appends a sentence. If `raw` is given, the tokens are located in it
+    /// instead of being joined.
+    fn push_sentence(&mut self, words: Vec<Word>, raw: Option<&str>) -> Result<()> {
+        if words.is_empty() {
+            return Ok(());
+        }
+        if !self.text.is_empty() {
+            self.text.push('\n');
+        }
+        let offset = self.text.chars().count();
+        let spans: Vec<Span> = match raw {
+            Some(raw) => {
+                let forms: Vec<&str> = words.iter().map(|w| w.form.as_str()).collect();
+                let mut spans = vec![];
+                for (word, fragments) in words.iter().zip(get_original_spans(&forms, raw)) {
+                    match (fragments.first(), fragments.last()) {
+                        (Some(first), Some(last)) => {
+                            spans.push((offset + first.0, offset + last.1))
+                        }
+                        _ => {
+                            return Err(Error::Parse {
+                                line: Some(word.line),
+                                message: format!("token {:?} is not found in `# text`", word.form),
+                            })
+                        }
+                    }
+                }
+                self.text.push_str(raw);
+                spans
+            }
+            None => {
+                let mut spans = vec![];
+                let mut cur = offset;
+                for (i, word) in words.iter().enumerate() {
+                    let len = word.form.chars().count();
+                    spans.push((cur, cur + len));
+                    self.text.push_str(&word.form);
+                    cur += len;
+                    if word.space_after && i + 1 < words.len() {
+                        self.text.push(' ');
+                        cur += 1;
+                    }
+                }
+                spans
+            }
+        };
+        let tags: Vec<&str> = words.iter().map(|w| w.tag.as_str()).collect();
+        self.entities.extend(decode_tags(&tags, &spans));
+        self.sentences
+            .push((offset, spans.last().map_or(offset, |s| s.1)));
+        for (word, span) in words.into_iter().zip(spans) {
+            self.tokens.push(Token {
+                span,
+                form: word.form,
+                columns: word.columns,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Reads a CoNLL-2003 style file: one token per line with whitespace
+/// separated columns, the first being the form and the last the entity tag
+/// (IOB1, BIO or BIOES), and blank lines between sentences. `-DOCSTART-`
+/// lines start a new document.
+///
+/// # Examples
+///
+/// ```
+/// use textspan::LabeledSpan;
+/// let conll = "-DOCSTART- -X- -X- O\n\n\
+///              EU NNP B-NP B-ORG\nrejects VBZ B-VP O\nGerman JJ B-NP B-MISC\ncall NN I-NP O\n\n\
+///              Peter NNP B-NP B-PER\nBlackburn NNP I-NP I-PER\n";
+/// let docs = textspan::formats::conll::read_conll2003(conll.as_bytes()).unwrap();
+/// assert_eq!(docs[0].text, "EU rejects German call\nPeter Blackburn");
+/// assert_eq!(
+///     docs[0].entities,
+///     [
+///         LabeledSpan::new(0, 2, "ORG"),
+///         LabeledSpan::new(11, 17, "MISC"),
+///         LabeledSpan::new(23, 38, "PER")
+///     ]
+/// );
+/// ```
+pub fn read_conll2003<R: Read>(reader: R) -> Result<Vec<Document>> {
+    let mut docs = vec![];
+    let mut doc = Document::default();
+    let mut words = vec![];
+    for (i, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        let columns: Vec<String> = line.split_whitespace().map(|s| s.to_string()).collect();
+        if columns.is_empty() {
+            doc.push_sentence(std::mem::take(&mut words), None)?;
+            continue;
+        }
+        if columns[0] == "-DOCSTART-" {
+            doc.push_sentence(std::mem::take(&mut words), None)?;
+            if !doc.is_empty() {
+                docs.push(std::mem::take(&mut doc));
+            }
+            continue;
+        }
+        let tag = if columns.len() > 1 {
+            columns[columns.len() - 1].clone()
+        } else {
+            "O".to_string()
+        };
+        words.push(Word {
+            form: columns[0].clone(),
+            columns,
+            tag,
+            space_after: true,
+            line: i + 1,
+        });
+    }
+    doc.push_sentence(words, None)?;
+    if !doc.is_empty() {
+        docs.push(doc);
+    }
+    Ok(docs)
+}
+
+/// Reads a CoNLL-U file.
+///
+/// The text of a sentence is taken from its `# text =` comment if any, or
+/// rebuilt from the forms honoring `SpaceAfter=No`. `# newdoc` comments start a
+/// new document. Multiword tokens (`1-2`) become single tokens spanning their
+/// surface form, and empty nodes (`1.1`) are skipped.
+///
+/// Entity tags are read from the `tag_key` attribute of the MISC column (e.g.
+/// `NER=B-PER` for `tag_key = "NER"`); tokens without it are outside entities.
+/// For a multiword token, the tag of its first word is used.
+///
+/// # Examples
+///
+/// ```
+/// use textspan::LabeledSpan;
+/// let conllu = "# text = Vámonos al mar.\n\
+///               1\tVámonos\tir\tVERB\t_\t_\t0\troot\t_\t_\n\
+///               2-3\tal\t_\t_\t_\t_\t_\t_\t_\t_\n\
+///               2\ta\ta\tADP\t_\t_\t4\tcase\t_\t_\n\
+///               3\tel\tel\tDET\t_\t_\t4\tdet\t_\t_\n\
+///               4\tmar\tmar\tNOUN\t_\t_\t1\tobl\t_\tSpaceAfter=No|NER=B-LOC\n\
+///               5\t.\t.\tPUNCT\t_\t_\t1\tpunct\t_\t_\n";
+/// let docs = textspan::formats::conll::read_conllu(conllu.as_bytes(), "NER").unwrap();
+/// assert_eq!(docs[0].text, "Vámonos al mar.");
+/// assert_eq!(docs[0].token_spans(), [(0, 7), (8, 10), (11, 14), (14, 15)]);
+/// assert_eq!(docs[0].entities, [LabeledSpan::new(11, 14, "LOC")]);
+/// ```
+pub fn read_conllu<R: Read>(reader: R, tag_key: &str) -> Result<Vec<Document>> {
+    let mut docs = vec![];
+    let mut doc = Document::default();
+    let mut words = vec![];
+    let mut raw: Option<String> = None;
+    // end of the multiword token being read, and the index of its entry
+    let mut multiword: Option<(usize, usize)> = None;
+    for (i, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        let lineno = i + 1;
+        if line.trim().is_empty() {
+            doc.push_sentence(std::mem::take(&mut words), raw.take().as_deref())?;
+            continue;
+        }
+        if let Some(comment) = line.strip_prefix('#') {
+            let comment = comment.trim();
+            if comment.starts_with("newdoc") {
+                doc.push_sentence(std::mem::take(&mut words), raw.take().as_deref())?;
+                if !doc.is_empty() {
+                    docs.push(std::mem::take(&mut doc));
+                }
+            } else if let Some(text) = comment.strip_prefix("text =") {
+                raw = Some(text.trim().to_string());
+            }
+            continue;
+        }
+        let columns: Vec<String> = line.split('\t').map(|s| s.to_string()).collect();
+        if columns.len() != 10 {
+            return Err(Error::Parse {
+                line: Some(lineno),
+                message: format!("expected 10 columns, found {}", columns.len()),
+            });
+        }
+        let misc: Vec<&str> = columns[9].split('|').collect();
+        let space_after = !misc.contains(&"SpaceAfter=No");
+        let tag = misc
+            .iter()
+            .find_map(|attr| attr.strip_prefix(tag_key)?.strip_prefix('='))
+            .unwrap_or("O")
+            .to_string();
+        let id = columns[0].as_str();
+        let invalid_id = || Error::Parse {
+            line: Some(lineno),
+            message: format!("invalid ID {:?}", id),
+        };
+        if id.contains('.') {
+            continue;
+        }
+        if let Some((_, end)) = id.split_once('-') {
+            let end: usize = end.parse().map_err(|_| invalid_id())?;
+            multiword = Some((end, words.len()));
+            words.push(Word {
+                form: columns[1].clone(),
+                columns,
+                tag: String::new(),
+                space_after,
+                line: lineno,
+            });
+            continue;
+        }
+        let n: usize = id.parse().map_err(|_| invalid_id())?;
+        match multiword {
+            Some((end, index)) if n <= end => {
+                let word = &mut words[index];
+                if word.tag.is_empty() {
+                    word.tag = tag;
+                }
+                if n == end {
+                    multiword = None;
+                }
+            }
+            _ => words.push(Word {
+                form: columns[1].clone(),
+                columns,
+                tag,
+                space_after,
+                line: lineno,
+            }),
+        }
+    }
+    doc.push_sentence(words, raw.as_deref())?;
+    if !doc.is_empty() {
+        docs.push(doc);
+    }
+    Ok(docs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_conll2003_documents() {
+        let conll = "-DOCSTART- -X- O O\n\nA B-X\nb I-X\n\n\n-DOCSTART- -X- O O\n\nc\nd I-Y\n";
+        let docs = read_conll2003(conll.as_bytes()).unwrap();
+        assert_eq!(docs.len(), 2);
+        assert_eq!(docs[0].text, "A b");
+        assert_eq!(docs[0].entities, [LabeledSpan::new(0, 3, "X")]);
+        assert_eq!(docs[1].text, "c d");
+        assert_eq!(docs[1].sentences, [(0, 3)]);
+        assert_eq!(docs[1].entities, [LabeledSpan::new(2, 3, "Y")]);
+        assert!(read_conll2003("".as_bytes()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn read_conllu_without_text() {
+        let conllu = "# newdoc id = a\n\
+                      1\tHello\t_\t_\t_\t_\t_\t_\t_\tSpaceAfter=No\n\
+                      2\t,\t_\t_\t_\t_\t_\t_\t_\t_\n\
+                      2.1\tghost\t_\t_\t_\t_\t_\t_\t_\t_\n\
+                      3\tworld\t_\t_\t_\t_\t_\t_\t_\tNE=S-LOC\n\
+                      \n\
+                      1\tBye\t_\t_\t_\t_\t_\t_\t_\t_\n\
+                      \n\
+                      # newdoc id = b\n\
+                      1\tNext\t_\t_\t_\t_\t_\t_\t_\t_\n";
+        let docs = read_conllu(conllu.as_bytes(), "NE").unwrap();
+        assert_eq!(docs.len(), 2);
+        assert_eq!(docs[0].text, "Hello, world\nBye");
+        assert_eq!(docs[0].sentences, [(0, 12), (13, 16)]);
+        assert_eq!(docs[0].entities, [LabeledSpan::new(7, 12, "LOC")]);
+        assert_eq!(docs[0].tokens[2].columns[9], "NE=S-LOC");
+        assert_eq!(docs[1].text, "Next");
+    }
+
+    #[test]
+    fn read_conllu_errors() {
+        let conllu = "1\tfoo\t_\n";
+        assert!(matches!(
+            read_conllu(conllu.as_bytes(), "NER"),
+            Err(Error::Parse { line: Some(1), .. })
+        ));
+        let conllu = "# text = foo\n1\tbar\t_\t_\t_\t_\t_\t_\t_\t_\n";
+        assert!(matches!(
+            read_conllu(conllu.as_bytes(), "NER"),
+            Err(Error::Parse { line: Some(2), .. })
+        ));
+        // the error points at the token missing from `# text`
+        let conllu = "# text = a b\n\
+                      1\ta\t_\t_\t_\t_\t_\t_\t_\t_\n\
+                      2\tc\t_\t_\t_\t_\t_\t_\t_\t_\n\
+                      3\tb\t_\t_\t_\t_\t_\t_\t_\t_\n";
+        let conllu = format!("# text = x\n1\tx\t_\t_\t_\t_\t_\t_\t_\t_\n\n{}", conllu);
+        assert!(matches!(
+            read_conllu(conllu.as_bytes(), "NER"),
+            Err(Error::Parse { line: Some(6), .. })
+        ));
+    }
+}
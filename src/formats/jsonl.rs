@@ -0,0 +1,348 @@
+//! JSONL span annotations, as exported by Prodigy and Doccano.
+//!
+//! Each line is an object with a `text` and its spans in one of these
+//! schemas:
+//!
+//! - Prodigy: `"spans": [{"start": 0, "end": 5, "label": "ORG"}]`
+//! - Doccano: `"label": [[0, 5, "ORG"]]`, or
+//!   `"entities": [{"start_offset": 0, "end_offset": 5, "label": "ORG"}]`
+//!
+//! Tools disagree on what the offsets count, so the unit must be given
+//! explicitly with [`OffsetUnit`]. Requires the `jsonl` feature.
+use super::{Error, Result};
+use crate::LabeledSpan;
+use serde_json::{json, Map, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+
+/// What the offsets in a file count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OffsetUnit {
+    /// Unicode scalar values, as in Doccano and Python strings.
+    Char,
+    /// UTF-8 bytes.
+    Byte,
+    /// UTF-16 code units, as in JavaScript and Java strings.
+    Utf16,
+}
+
+impl OffsetUnit {
+    /// Returns the offset of every char boundary of `text`, including its end.
+    fn boundaries(self, text: &str) -> Vec<usize> {
+        let mut ret = Vec::with_capacity(text.len() + 1);
+        let mut offset = 0;
+        for c in text.chars() {
+            ret.push(offset);
+            offset += match self {
+                OffsetUnit::Char => 1,
+                OffsetUnit::Byte => c.len_utf8(),
+                OffsetUnit::Utf16 => c.len_utf16(),
+            };
+        }
+        ret.push(offset);
+        ret
+    }
+}
+
+/// The schema used by [`write_jsonl`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schema {
+    /// `"spans": [{"start": 0, "end": 5, "label": "ORG"}]`
+    Prodigy,
+    /// `"label": [[0, 5, "ORG"]]`
+    Doccano,
+}
+
+/// One line of a JSONL file. `spans` are char spans of `text`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Record {
+    pub text: String,
+    pub spans: Vec<LabeledSpan>,
+    /// The other fields of the line, kept as they are.
+    pub meta: Map<String, Value>,
+}
+
+fn parse_err(line: usize, message: impl Into<String>) -> Error {
+    Error::Parse {
+        line: Some(line),
+        message: message.into(),
+    }
+}
+
+/// `(start, end, label, text)` of a span as written in a line.
+type RawSpan = (u64, u64, String, Option<String>);
+
+/// Reads `(start, end, label, text)` from each span of `value`, which is the
+/// `spans`, `entities` or `label` field of a line.
+fn raw_spans(value: &Value, keys: (&str, &str), line: usize) -> Result<Vec<RawSpan>> {
+    let items = value
+        .as_array()
+        .ok_or_else(|| parse_err(line, "spans must be an array"))?;
+    items
+        .iter()
+        .map(|item| {
+            let get = |v: Option<&Value>| v.and_then(Value::as_u64);
+            fn label(v: Option<&Value>) -> &str {
+                v.and_then(Value::as_str).unwrap_or_default()
+            }
+            let (start, end, label, text) = match item {
+                Value::Array(a) => (get(a.first()), get(a.get(1)), label(a.get(2)), None),
+                Value::Object(o) => (
+                    get(o.get(keys.0)),
+                    get(o.get(keys.1)),
+                    label(o.get("label")),
+                    o.get("text").and_then(Value::as_str),
+                ),
+                _ => (None, None, "", None),
+            };
+            match (start, end) {
+                (Some(start), Some(end)) => {
+                    Ok((start, end, label.to_string(), text.map(str::to_string)))
+                }
+                _ => Err(parse_err(line, format!("invalid span {}", item))),
+            }
+        })
+        .collect()
+}
+
+/// Reads a JSONL file of span annotations whose offsets count `unit`,
+/// converting them to char spans.
+///
+/// Every span must be on char boundaries of the text and must not be inverted.
+/// If a span also has a `text` field, it must match the text it covers.
+///
+/// # Examples
+///
+/// ```
+/// use textspan::formats::jsonl::{read_jsonl, OffsetUnit};
+/// use textspan::LabeledSpan;
+/// let jsonl = r#"{"text": "東京", "spans": [{"start": 0, "end": 6, "label": "LOC"}]}"#;
+/// let records = read_jsonl(jsonl.as_bytes(), OffsetUnit::Byte).unwrap();
+/// assert_eq!(records[0].spans, [LabeledSpan::new(0, 2, "LOC")]);
+/// assert!(read_jsonl(jsonl.as_bytes(), OffsetUnit::Char).is_err());
+/// ```
+pub fn read_jsonl<R: Read>(reader: R, unit: OffsetUnit) -> Result<Vec<Record>> {
+    let mut ret = vec![];
+    for (i, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        let lineno = i + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value = serde_json::from_str(&line).map_err(|e| parse_err(lineno, e.to_string()))?;
+        let mut meta = match value {
+            Value::Object(o) => o,
+            _ => return Err(parse_err(lineno, "expected an object")),
+        };
+        let text = match meta.remove("text") {
+            Some(Value::String(s)) => s,
+            _ => return Err(parse_err(lineno, "missing text")),
+        };
+        let raw = if let Some(spans) = meta.remove("spans") {
+            raw_spans(&spans, ("start", "end"), lineno)?
+        } else if let Some(entities) = meta.remove("entities") {
+            raw_spans(&entities, ("start_offset", "end_offset"), lineno)?
+        } else {
+            // Doccano also uses `label` for document categories.
+            match meta.get("label") {
+                Some(Value::Array(a)) if a.iter().all(Value::is_array) => {
+                    let label = meta.remove("label").unwrap();
+                    raw_spans(&label, ("", ""), lineno)?
+                }
+                _ => vec![],
+            }
+        };
+
+        let boundaries = unit.boundaries(&text);
+        let to_char = |offset: u64| boundaries.binary_search(&(offset as usize)).ok();
+        let chars: Vec<char> = text.chars().collect();
+        let mut spans = vec![];
+        for (start, end, label, covered) in raw {
+            let span = match (to_char(start), to_char(end)) {
+                (Some(l), Some(r)) if l <= r => (l, r),
+                _ => {
+                    return Err(Error::InvalidSpan {
+                        line: Some(lineno),
+                        span: (start as usize, end as usize),
+                        message: format!("not a valid span of the text in {:?} offsets", unit),
+                    })
+                }
+            };
+            if let Some(covered) = covered {
+                let actual: String = chars[span.0..span.1].iter().collect();
+                if actual != covered {
+                    return Err(Error::InvalidSpan {
+                        line: Some(lineno),
+                        span: (start as usize, end as usize),
+                        message: format!("span has text {:?}, but covers {:?}", covered, actual),
+                    });
+                }
+            }
+            spans.push(LabeledSpan { span, label });
+        }
+        ret.push(Record { text, spans, meta });
+    }
+    Ok(ret)
+}
+
+/// Writes `records` as JSONL in `schema`, with offsets counting `unit`.
+///
+/// Fields in [`Record::meta`] are written as they are, except for the ones
+/// that hold the spans in `schema`.
+///
+/// # Examples
+///
+/// ```
+/// use textspan::formats::jsonl::{write_jsonl, OffsetUnit, Record, Schema};
+/// use textspan::LabeledSpan;
+/// let record = Record {
+///     text: "😀 Tokyo".to_string(),
+///     spans: vec![LabeledSpan::new(2, 7, "LOC")],
+///     ..Default::default()
+/// };
+/// let mut buf = vec![];
+/// write_jsonl(&mut buf, &[record], Schema::Doccano, OffsetUnit::Utf16).unwrap();
+/// assert_eq!(
+///     String::from_utf8(buf).unwrap(),
+///     "{\"label\":[[3,8,\"LOC\"]],\"text\":\"😀 Tokyo\"}\n"
+/// );
+/// ```
+pub fn write_jsonl<W: Write>(
+    mut writer: W,
+    records: &[Record],
+    schema: Schema,
+    unit: OffsetUnit,
+) -> Result<()> {
+    for record in records {
+        let boundaries = unit.boundaries(&record.text);
+        let mut spans = vec![];
+        for s in &record.spans {
+            let (l, r) = s.span;
+            if l > r || r >= boundaries.len() {
+                return Err(Error::InvalidSpan {
+                    line: None,
+                    span: s.span,
+                    message: format!("out of the text of length {}", boundaries.len() - 1),
+                });
+            }
+            let (start, end) = (boundaries[l], boundaries[r]);
+            spans.push(match schema {
+                Schema::Prodigy => json!({"start": start, "end": end, "label": s.label}),
+                Schema::Doccano => json!([start, end, s.label]),
+            });
+        }
+        let mut obj = record.meta.clone();
+        obj.insert("text".to_string(), Value::String(record.text.clone()));
+        let key = match schema {
+            Schema::Prodigy => "spans",
+            Schema::Doccano => "label",
+        };
+        obj.insert(key.to_string(), Value::Array(spans));
+        serde_json::to_writer(&mut writer, &obj)?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_jsonl_schemas() {
+        let jsonl = r#"{"text": "I ❤ Tokyo", "spans": [{"start": 4, "end": 9, "label": "LOC", "text": "Tokyo"}], "meta": {"source": "x"}}
+
+{"id": 1, "text": "I ❤ Tokyo", "label": [[4, 9, "LOC"]]}
+{"text": "I ❤ Tokyo", "entities": [{"id": 3, "start_offset": 4, "end_offset": 9, "label": "LOC"}]}
+{"text": "I ❤ Tokyo", "label": ["positive"]}
+"#;
+        let records = read_jsonl(jsonl.as_bytes(), OffsetUnit::Char).unwrap();
+        assert_eq!(records.len(), 4);
+        for record in &records[..3] {
+            assert_eq!(record.spans, [LabeledSpan::new(4, 9, "LOC")]);
+        }
+        assert_eq!(records[0].meta["meta"]["source"], "x");
+        assert_eq!(records[1].meta["id"], 1);
+        assert!(records[3].spans.is_empty());
+        assert_eq!(records[3].meta["label"][0], "positive");
+    }
+
+    #[test]
+    fn jsonl_roundtrip_units() {
+        let record = Record {
+            text: "😀 Straße in 東京".to_string(),
+            spans: vec![LabeledSpan::new(2, 8, "A"), LabeledSpan::new(12, 14, "B")],
+            meta: Map::new(),
+        };
+        for &unit in &[OffsetUnit::Char, OffsetUnit::Byte, OffsetUnit::Utf16] {
+            for &schema in &[Schema::Prodigy, Schema::Doccano] {
+                let mut buf = vec![];
+                write_jsonl(&mut buf, std::slice::from_ref(&record), schema, unit).unwrap();
+                let records = read_jsonl(&buf[..], unit).unwrap();
+                assert_eq!(
+                    records,
+                    std::slice::from_ref(&record),
+                    "{:?} {:?}",
+                    unit,
+                    schema
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn read_jsonl_rejects_bad_spans() {
+        for (input, unit) in [
+            (
+                r#"{"text": "😀", "label": [[0, 1, "X"]]}"#,
+                OffsetUnit::Utf16,
+            ),
+            (r#"{"text": "é", "label": [[1, 2, "X"]]}"#, OffsetUnit::Byte),
+            (
+                r#"{"text": "abc", "label": [[2, 1, "X"]]}"#,
+                OffsetUnit::Char,
+            ),
+            (
+                r#"{"text": "abc", "label": [[0, 4, "X"]]}"#,
+                OffsetUnit::Char,
+            ),
+            (
+                r#"{"text": "abc", "spans": [{"start": 0, "end": 1, "text": "b"}]}"#,
+                OffsetUnit::Char,
+            ),
+        ] {
+            assert!(
+                matches!(
+                    read_jsonl(input.as_bytes(), unit),
+                    Err(Error::InvalidSpan { line: Some(1), .. })
+                ),
+                "{}",
+                input
+            );
+        }
+        for input in [
+            "[1]",
+            "{",
+            r#"{"spans": []}"#,
+            r#"{"text": "a", "spans": [{}]}"#,
+        ] {
+            assert!(
+                matches!(
+                    read_jsonl(input.as_bytes(), OffsetUnit::Char),
+                    Err(Error::Parse { line: Some(1), .. })
+                ),
+                "{}",
+                input
+            );
+        }
+        assert!(matches!(
+            read_jsonl("{\"text\": \"a\"}\nnot json\n".as_bytes(), OffsetUnit::Char),
+            Err(Error::Parse { line: Some(2), .. })
+        ));
+        let record = Record {
+            text: "ab".to_string(),
+            spans: vec![LabeledSpan::new(1, 3, "X")],
+            ..Default::default()
+        };
+        assert!(write_jsonl(vec![], &[record], Schema::Prodigy, OffsetUnit::Char).is_err());
+    }
+}
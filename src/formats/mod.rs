@@ -0,0 +1,241 @@
+//! Readers and writers for span annotation formats.
+//!
+//! Spans read from these formats are char spans, like everywhere else in this
+//! crate, so that they can be passed directly to e.g.
+//! [`align_spans`](crate::align_spans) or
+//! [`lift_spans_index`](crate::lift_spans_index).
+pub mod brat;
+pub mod conll;
+#[cfg(feature = "jsonl")]
+pub mod jsonl;
+#[cfg(feature = "spacy")]
+pub mod spacy;
+pub mod webanno;
+
+use crate::{lift_span_index, LabeledSpan, Span};
+use std::fmt;
+use std::io;
+
+/// Error returned by the readers and writers in this module.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    Io(io::Error),
+    #[cfg(any(feature = "spacy", feature = "jsonl"))]
+    Json(serde_json::Error),
+    /// Malformed input. `line` is the 1-based line number, if the format is
+    /// line oriented.
+    Parse {
+        line: Option<usize>,
+        message: String,
+    },
+    /// A span that does not fit the text or the tokens it is annotated on.
+    /// `line` is the 1-based line number of the span, if the format is line
+    /// oriented.
+    InvalidSpan {
+        line: Option<usize>,
+        span: Span,
+        message: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            #[cfg(any(feature = "spacy", feature = "jsonl"))]
+            Error::Json(e) => write!(f, "{}", e),
+            Error::Parse {
+                line: Some(line),
+                message,
+            } => write!(f, "line {}: {}", line, message),
+            Error::Parse {
+                line: None,
+                message,
+            } => write!(f, "{}", message),
+            Error::InvalidSpan {
+                line: Some(line),
+                span,
+                message,
+            } => write!(f, "line {}: invalid span {:?}: {}", line, span, message),
+            Error::InvalidSpan {
+                line: None,
+                span,
+                message,
+            } => write!(f, "invalid span {:?}: {}", span, message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            #[cfg(any(feature = "spacy", feature = "jsonl"))]
+            Error::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+#[cfg(any(feature = "spacy", feature = "jsonl"))]
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Tagging scheme for token-level entity tags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagScheme {
+    /// `B-X`, `I-X` and `O`.
+    Bio,
+    /// `B-X`, `I-X`, `L-X`, `U-X` and `O`, also known as BIOES.
+    Biluo,
+}
+
+/// Converts token-level entity tags to labeled spans over `tokens`.
+///
+/// Accepts BIO, BILUO and BIOES tags (`E-` and `S-` are read as `L-` and
+/// `U-`). An `I-` or `L-` tag that does not continue an entity with the same
+/// label starts a new one. `-` (missing) is treated as `O`.
+///
+/// # Examples
+///
+/// ```
+/// use textspan::formats::decode_tags;
+/// use textspan::LabeledSpan;
+/// let tokens = [(0, 6), (7, 11), (12, 15), (16, 22)];
+/// let tags = ["B-PER", "L-PER", "O", "U-LOC"];
+/// assert_eq!(
+///     decode_tags(&tags, &tokens),
+///     [LabeledSpan::new(0, 11, "PER"), LabeledSpan::new(16, 22, "LOC")]
+/// );
+/// ```
+pub fn decode_tags<S: AsRef<str>>(tags: &[S], tokens: &[Span]) -> Vec<LabeledSpan> {
+    let mut ret = vec![];
+    let mut cur: Option<LabeledSpan> = None;
+    for (tag, &(start, end)) in tags.iter().zip(tokens) {
+        let tag = tag.as_ref();
+        let (prefix, label) = match tag.find('-') {
+            Some(p) if p > 0 => (&tag[..p], &tag[p + 1..]),
+            _ => (tag, ""),
+        };
+        let continues = matches!(&cur, Some(ent) if ent.label == label);
+        match prefix {
+            "I" | "L" | "E" if continues => {
+                if let Some(ent) = cur.as_mut() {
+                    ent.span.1 = end;
+                }
+            }
+            "B" | "I" | "L" | "E" | "U" | "S" => {
+                ret.extend(cur.take());
+                cur = Some(LabeledSpan::new(start, end, label));
+            }
+            _ => ret.extend(cur.take()),
+        }
+        if matches!(prefix, "L" | "E" | "U" | "S") {
+            ret.extend(cur.take());
+        }
+    }
+    ret.extend(cur);
+    ret
+}
+
+/// Converts labeled spans to token-level entity tags over `tokens`.
+///
+/// Every entity must start and end at token boundaries, and entities must not
+/// overlap each other.
+///
+/// # Examples
+///
+/// ```
+/// use textspan::formats::{encode_tags, TagScheme};
+/// use textspan::LabeledSpan;
+/// let tokens = [(0, 6), (7, 11), (12, 15), (16, 22)];
+/// let entities = [LabeledSpan::new(0, 11, "PER"), LabeledSpan::new(16, 22, "LOC")];
+/// assert_eq!(
+///     encode_tags(&entities, &tokens, TagScheme::Biluo).unwrap(),
+///     ["B-PER", "L-PER", "O", "U-LOC"]
+/// );
+/// assert_eq!(
+///     encode_tags(&entities, &tokens, TagScheme::Bio).unwrap(),
+///     ["B-PER", "I-PER", "O", "B-LOC"]
+/// );
+/// ```
+pub fn encode_tags(
+    entities: &[LabeledSpan],
+    tokens: &[Span],
+    scheme: TagScheme,
+) -> Result<Vec<String>> {
+    let mut tags = vec!["O".to_string(); tokens.len()];
+    let mut filled = vec![false; tokens.len()];
+    for entity in entities {
+        let (l, r) = match lift_span_index(entity.span, tokens) {
+            (Ok(l), Ok(r)) if l < r => (l, r),
+            _ => {
+                return Err(Error::InvalidSpan {
+                    line: None,
+                    span: entity.span,
+                    message: "entity does not match token boundaries".to_string(),
+                })
+            }
+        };
+        if filled[l..r].iter().any(|&x| x) {
+            return Err(Error::InvalidSpan {
+                line: None,
+                span: entity.span,
+                message: "entity overlaps another entity".to_string(),
+            });
+        }
+        for i in l..r {
+            let prefix = match scheme {
+                TagScheme::Biluo if r - l == 1 => "U",
+                TagScheme::Biluo if i == r - 1 => "L",
+                _ if i == l => "B",
+                _ => "I",
+            };
+            tags[i] = format!("{}-{}", prefix, entity.label);
+            filled[i] = true;
+        }
+    }
+    Ok(tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_tags_handmade() {
+        let tokens: Vec<Span> = (0..6).map(|i| (i * 2, i * 2 + 1)).collect();
+        let tags = ["I-A", "I-B", "E-B", "S-A", "-", "B-A"];
+        assert_eq!(
+            decode_tags(&tags, &tokens),
+            [
+                LabeledSpan::new(0, 1, "A"),
+                LabeledSpan::new(2, 5, "B"),
+                LabeledSpan::new(6, 7, "A"),
+                LabeledSpan::new(10, 11, "A"),
+            ]
+        );
+        assert_eq!(decode_tags::<&str>(&[], &[]), []);
+    }
+
+    #[test]
+    fn encode_tags_rejects_invalid_entities() {
+        let tokens = [(0, 3), (4, 7)];
+        let misaligned = [LabeledSpan::new(1, 3, "A")];
+        assert!(encode_tags(&misaligned, &tokens, TagScheme::Bio).is_err());
+        let overlapping = [LabeledSpan::new(0, 7, "A"), LabeledSpan::new(4, 7, "B")];
+        assert!(encode_tags(&overlapping, &tokens, TagScheme::Bio).is_err());
+    }
+}
@@ -0,0 +1,612 @@
+//! spaCy's JSON training format and binary `.spacy` (`DocBin`) files.
+//!
+//! Requires the `spacy` feature.
+use super::{decode_tags, encode_tags, Error, Result, TagScheme};
+use crate::{get_original_spans, LabeledSpan, Span};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+use std::io::{Read, Write};
+
+mod symbols;
+
+/// A document with its tokens and entities, as char spans of `text`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Doc {
+    pub text: String,
+    pub tokens: Vec<Span>,
+    pub entities: Vec<LabeledSpan>,
+}
+
+impl Doc {
+    /// Returns the text of each token.
+    pub fn token_texts(&self) -> Vec<String> {
+        let chars: Vec<char> = self.text.chars().collect();
+        self.tokens
+            .iter()
+            .map(|&(l, r)| chars[l..r].iter().collect())
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonDoc {
+    #[serde(default)]
+    id: usize,
+    paragraphs: Vec<JsonParagraph>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonParagraph {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    raw: Option<String>,
+    sentences: Vec<JsonSentence>,
+    #[serde(default)]
+    cats: Vec<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    entities: Option<Vec<(usize, usize, String)>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonSentence {
+    tokens: Vec<JsonToken>,
+    #[serde(default)]
+    brackets: Vec<serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonToken {
+    id: usize,
+    orth: String,
+    /// `" "` or `""` in spaCy v3, a bool in some older files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    space: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ner: Option<String>,
+}
+
+/// Reads documents in spaCy's JSON training format, as produced by
+/// `spacy convert` or `spacy.training.docs_to_json`. Each paragraph becomes
+/// one [`Doc`].
+///
+/// If a paragraph has `raw` text, tokens are located in it with
+/// [`get_original_spans`], so that `raw` may differ slightly from the token
+/// texts. Otherwise, the text is rebuilt from the tokens and their `space`.
+/// Entities are read from the token `ner` tags, or from the paragraph
+/// `entities` if no token has one.
+///
+/// # Examples
+///
+/// ```
+/// use textspan::LabeledSpan;
+/// let json = r#"[{"id": 0, "paragraphs": [{"raw": "I like  Tokyo.", "sentences": [{"tokens": [
+///     {"id": 0, "orth": "I", "ner": "O"},
+///     {"id": 1, "orth": "like", "ner": "O"},
+///     {"id": 2, "orth": "Tokyo", "ner": "U-GPE"},
+///     {"id": 3, "orth": ".", "ner": "O"}
+/// ]}]}]}]"#;
+/// let docs = textspan::formats::spacy::read_json(json.as_bytes()).unwrap();
+/// assert_eq!(docs[0].tokens, [(0, 1), (2, 6), (8, 13), (13, 14)]);
+/// assert_eq!(docs[0].entities, [LabeledSpan::new(8, 13, "GPE")]);
+/// ```
+pub fn read_json<R: Read>(reader: R) -> Result<Vec<Doc>> {
+    let json: Vec<JsonDoc> = serde_json::from_reader(reader)?;
+    let mut docs = vec![];
+    for paragraph in json.into_iter().flat_map(|doc| doc.paragraphs) {
+        let tokens: Vec<_> = paragraph
+            .sentences
+            .into_iter()
+            .flat_map(|sent| sent.tokens)
+            .collect();
+        let orths: Vec<&str> = tokens.iter().map(|t| t.orth.as_str()).collect();
+        let (text, spans) = match paragraph.raw {
+            Some(raw) => {
+                let mut spans = vec![];
+                for (orth, fragments) in orths.iter().zip(get_original_spans(&orths, &raw)) {
+                    match (fragments.first(), fragments.last()) {
+                        (Some(first), Some(last)) => spans.push((first.0, last.1)),
+                        _ => {
+                            return Err(Error::Parse {
+                                line: None,
+                                message: format!("token {:?} is not found in the raw text", orth),
+                            })
+                        }
+                    }
+                }
+                (raw, spans)
+            }
+            None => {
+                let mut text = String::new();
+                let mut spans = vec![];
+                let mut cur = 0;
+                for token in &tokens {
+                    let len = token.orth.chars().count();
+                    spans.push((cur, cur + len));
+                    text.push_str(&token.orth);
+                    cur += len;
+                    let space = match &token.space {
+                        Some(serde_json::Value::String(s)) => s.clone(),
+                        Some(serde_json::Value::Bool(false)) => String::new(),
+                        _ => " ".to_string(),
+                    };
+                    cur += space.chars().count();
+                    text.push_str(&space);
+                }
+                (text, spans)
+            }
+        };
+        let entities = if tokens.iter().any(|t| t.ner.is_some()) {
+            let tags: Vec<_> = tokens
+                .iter()
+                .map(|t| t.ner.as_deref().unwrap_or("O"))
+                .collect();
+            decode_tags(&tags, &spans)
+        } else {
+            paragraph
+                .entities
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(l, r, label)| LabeledSpan::new(l, r, label))
+                .collect()
+        };
+        docs.push(Doc {
+            text,
+            tokens: spans,
+            entities,
+        });
+    }
+    Ok(docs)
+}
+
+/// Writes `docs` in spaCy's JSON training format, one paragraph per document
+/// with all its tokens in a single sentence.
+///
+/// Entities are written both as BILUO `ner` tags and as paragraph `entities`,
+/// and must therefore match token boundaries.
+pub fn write_json<W: Write>(writer: W, docs: &[Doc]) -> Result<()> {
+    let mut json = vec![];
+    for (i, doc) in docs.iter().enumerate() {
+        let tags = encode_tags(&doc.entities, &doc.tokens, TagScheme::Biluo)?;
+        let chars: Vec<char> = doc.text.chars().collect();
+        let mut tokens = vec![];
+        for (j, (&(l, r), tag)) in doc.tokens.iter().zip(tags).enumerate() {
+            let next = doc.tokens.get(j + 1).map_or(chars.len(), |t| t.0);
+            if r < l || next < r || r > chars.len() {
+                return Err(Error::InvalidSpan {
+                    line: None,
+                    span: (l, r),
+                    message: "tokens must be sorted, non-overlapping and within the text"
+                        .to_string(),
+                });
+            }
+            let orth = chars[l..r].iter().collect();
+            let space = if next > r && chars[r] == ' ' { " " } else { "" };
+            tokens.push(JsonToken {
+                id: j,
+                orth,
+                space: Some(serde_json::Value::String(space.to_string())),
+                ner: Some(tag),
+            });
+        }
+        let entities = doc
+            .entities
+            .iter()
+            .map(|e| (e.span.0, e.span.1, e.label.clone()))
+            .collect();
+        json.push(JsonDoc {
+            id: i,
+            paragraphs: vec![JsonParagraph {
+                raw: Some(doc.text.clone()),
+                sentences: vec![JsonSentence {
+                    tokens,
+                    brackets: vec![],
+                }],
+                cats: vec![],
+                entities: Some(entities),
+            }],
+        });
+    }
+    serde_json::to_writer(writer, &json)?;
+    Ok(())
+}
+
+// Attribute IDs from `spacy.attrs`.
+const ORTH: u64 = 65;
+const ENT_IOB: u64 = 77;
+const ENT_TYPE: u64 = 78;
+
+// Values of the `ENT_IOB` attribute.
+const IOB_MISSING: u64 = 0;
+const IOB_I: u64 = 1;
+const IOB_O: u64 = 2;
+const IOB_B: u64 = 3;
+
+/// MurmurHash64A, which spaCy uses to hash strings with seed 1.
+fn murmurhash64a(key: &[u8], seed: u64) -> u64 {
+    const M: u64 = 0xc6a4_a793_5bd1_e995;
+    const R: u32 = 47;
+    let mut h = seed ^ (key.len() as u64).wrapping_mul(M);
+    let chunks = key.chunks_exact(8);
+    let tail = chunks.remainder();
+    for chunk in chunks {
+        let mut k = u64::from_le_bytes(chunk.try_into().unwrap());
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        h ^= k;
+        h = h.wrapping_mul(M);
+    }
+    if !tail.is_empty() {
+        for (i, &b) in tail.iter().enumerate() {
+            h ^= u64::from(b) << (8 * i);
+        }
+        h = h.wrapping_mul(M);
+    }
+    h ^= h >> R;
+    h = h.wrapping_mul(M);
+    h ^= h >> R;
+    h
+}
+
+/// Returns the key spaCy's `StringStore` assigns to `s`.
+fn string_key(s: &str) -> u64 {
+    if s.is_empty() {
+        return 0;
+    }
+    match symbols::SYMBOLS.binary_search_by_key(&s, |&(k, _)| k) {
+        Ok(i) => symbols::SYMBOLS[i].1,
+        Err(_) => murmurhash64a(s.as_bytes(), 1),
+    }
+}
+
+fn invalid_docbin(message: impl Into<String>) -> Error {
+    Error::Parse {
+        line: None,
+        message: message.into(),
+    }
+}
+
+fn map_get<'a>(map: &'a [(rmpv::Value, rmpv::Value)], key: &str) -> Option<&'a rmpv::Value> {
+    map.iter()
+        .find(|(k, _)| k.as_str() == Some(key))
+        .map(|(_, v)| v)
+}
+
+fn map_bytes<'a>(map: &'a [(rmpv::Value, rmpv::Value)], key: &str) -> Result<&'a [u8]> {
+    map_get(map, key)
+        .and_then(|v| v.as_slice())
+        .ok_or_else(|| invalid_docbin(format!("missing or invalid `{}`", key)))
+}
+
+/// Reads a `.spacy` file serialized with spaCy's `DocBin`.
+///
+/// Only the token texts, trailing spaces and entity annotations are read;
+/// other attributes, span groups and categories are ignored.
+pub fn read_docbin<R: Read>(reader: R) -> Result<Vec<Doc>> {
+    let mut decoder = flate2::read::ZlibDecoder::new(reader);
+    let msg = rmpv::decode::read_value(&mut decoder)
+        .map_err(|e| invalid_docbin(format!("invalid msgpack: {}", e)))?;
+    let msg = msg
+        .as_map()
+        .ok_or_else(|| invalid_docbin("DocBin must be a map"))?;
+
+    let attrs: Vec<u64> = map_get(msg, "attrs")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| invalid_docbin("missing `attrs`"))?
+        .iter()
+        .map(|v| v.as_u64().ok_or_else(|| invalid_docbin("invalid `attrs`")))
+        .collect::<Result<_>>()?;
+    let column = |attr| attrs.iter().position(|&a| a == attr);
+    let orth_col = column(ORTH).ok_or_else(|| invalid_docbin("`ORTH` is not stored"))?;
+    let ent_cols = column(ENT_IOB).zip(column(ENT_TYPE));
+
+    let mut strings: HashMap<u64, String> = HashMap::new();
+    for s in map_get(msg, "strings")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| invalid_docbin("missing `strings`"))?
+    {
+        let s = s
+            .as_str()
+            .ok_or_else(|| invalid_docbin("invalid `strings`"))?;
+        strings.insert(string_key(s), s.to_string());
+    }
+    strings.insert(0, String::new());
+    let lookup = |key: u64| {
+        strings
+            .get(&key)
+            .map(|s| s.as_str())
+            .ok_or_else(|| invalid_docbin(format!("unknown string key {}", key)))
+    };
+
+    let tokens: Vec<u64> = map_bytes(msg, "tokens")?
+        .chunks_exact(8)
+        .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+        .collect();
+    let spaces = map_bytes(msg, "spaces")?;
+    let lengths: Vec<usize> = map_bytes(msg, "lengths")?
+        .chunks_exact(4)
+        .map(|b| {
+            let len = i32::from_le_bytes(b.try_into().unwrap());
+            usize::try_from(len).map_err(|_| invalid_docbin(format!("negative length {}", len)))
+        })
+        .collect::<Result<_>>()?;
+    let flags = map_get(msg, "flags").and_then(|v| v.as_array());
+    let n_tokens = lengths
+        .iter()
+        .try_fold(0usize, |acc, &len| acc.checked_add(len));
+    let n_values = n_tokens.and_then(|n| n.checked_mul(attrs.len()));
+    if n_values != Some(tokens.len()) || n_tokens != Some(spaces.len()) {
+        return Err(invalid_docbin("inconsistent array sizes"));
+    }
+
+    let mut docs = vec![];
+    let mut row = 0;
+    for (i, &len) in lengths.iter().enumerate() {
+        let unknown_spaces = flags
+            .and_then(|flags| flags.get(i))
+            .and_then(|f| f.as_map())
+            .and_then(|f| map_get(f, "has_unknown_spaces"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let mut doc = Doc::default();
+        let mut tags = vec![];
+        let mut cur = 0;
+        for row in row..row + len {
+            let values = &tokens[row * attrs.len()..(row + 1) * attrs.len()];
+            let orth = lookup(values[orth_col])?;
+            let len = orth.chars().count();
+            doc.text.push_str(orth);
+            doc.tokens.push((cur, cur + len));
+            cur += len;
+            if unknown_spaces || spaces[row] != 0 {
+                doc.text.push(' ');
+                cur += 1;
+            }
+            if let Some((iob_col, type_col)) = ent_cols {
+                let label = lookup(values[type_col])?;
+                tags.push(match values[iob_col] {
+                    IOB_B => format!("B-{}", label),
+                    IOB_I => format!("I-{}", label),
+                    IOB_O | IOB_MISSING => "O".to_string(),
+                    v => return Err(invalid_docbin(format!("invalid ENT_IOB value {}", v))),
+                });
+            }
+        }
+        doc.entities = decode_tags(&tags, &doc.tokens);
+        docs.push(doc);
+        row += len;
+    }
+    Ok(docs)
+}
+
+/// Writes `docs` as a `.spacy` file that can be loaded with spaCy's
+/// `DocBin().from_disk(...)`.
+///
+/// The text between two tokens must be empty or a single space, or the gap
+/// becomes a token itself as spaCy's tokenizer does for extra whitespace.
+/// Entities must match token boundaries.
+///
+/// # Examples
+///
+/// ```
+/// use textspan::formats::spacy::{read_docbin, write_docbin, Doc};
+/// use textspan::LabeledSpan;
+/// let doc = Doc {
+///     text: "Apple is in California".to_string(),
+///     tokens: vec![(0, 5), (6, 8), (9, 11), (12, 22)],
+///     entities: vec![LabeledSpan::new(0, 5, "ORG"), LabeledSpan::new(12, 22, "GPE")],
+/// };
+/// let mut buf = vec![];
+/// write_docbin(&mut buf, &[doc.clone()]).unwrap();
+/// assert_eq!(read_docbin(&buf[..]).unwrap(), [doc]);
+/// ```
+pub fn write_docbin<W: Write>(writer: W, docs: &[Doc]) -> Result<()> {
+    use rmpv::Value;
+    let attrs = [ORTH, ENT_IOB, ENT_TYPE];
+    let mut strings = std::collections::BTreeSet::new();
+    let (mut tokens, mut spaces, mut lengths) = (vec![], vec![], vec![]);
+    for doc in docs {
+        let chars: Vec<char> = doc.text.chars().collect();
+        // fill the gaps between tokens, so that `text` is reconstructed
+        let mut words: Vec<(Span, bool)> = vec![];
+        let mut cur = 0;
+        for &(l, r) in doc.tokens.iter().chain(Some(&(chars.len(), chars.len()))) {
+            if l < cur || r < l || r > chars.len() {
+                return Err(Error::InvalidSpan {
+                    line: None,
+                    span: (l, r),
+                    message: "tokens must be sorted, non-overlapping and within the text"
+                        .to_string(),
+                });
+            }
+            let mut gap = cur;
+            if let Some(last) = words.last_mut() {
+                if gap < l && chars[gap] == ' ' {
+                    last.1 = true;
+                    gap += 1;
+                }
+            }
+            if gap < l {
+                words.push(((gap, l), false));
+            }
+            if l < r {
+                words.push(((l, r), false));
+            }
+            cur = r;
+        }
+        let spans: Vec<Span> = words.iter().map(|w| w.0).collect();
+        let tags = encode_tags(&doc.entities, &spans, TagScheme::Bio)?;
+        for (&((l, r), space), tag) in words.iter().zip(tags) {
+            let orth: String = chars[l..r].iter().collect();
+            let (iob, label) = match tag.split_at(1) {
+                ("B", label) => (IOB_B, &label[1..]),
+                ("I", label) => (IOB_I, &label[1..]),
+                _ => (IOB_O, ""),
+            };
+            tokens.extend(&[string_key(&orth), iob, string_key(label)]);
+            spaces.push(space as u8);
+            strings.insert(label.to_string());
+            strings.insert(orth);
+        }
+        lengths.push(words.len() as i32);
+    }
+    let to_bytes = |v: &[u64]| v.iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<_>>();
+    let string = |s: &str| Value::String(s.into());
+    let msg = Value::Map(vec![
+        (string("version"), string("0.1")),
+        (
+            string("attrs"),
+            Value::Array(attrs.iter().map(|&a| Value::from(a)).collect()),
+        ),
+        (string("tokens"), Value::Binary(to_bytes(&tokens))),
+        (string("spaces"), Value::Binary(spaces)),
+        (
+            string("lengths"),
+            Value::Binary(lengths.iter().flat_map(|x| x.to_le_bytes()).collect()),
+        ),
+        (
+            string("strings"),
+            Value::Array(strings.iter().map(|s| string(s)).collect()),
+        ),
+        (
+            string("cats"),
+            Value::Array(docs.iter().map(|_| Value::Map(vec![])).collect()),
+        ),
+        (
+            string("flags"),
+            Value::Array(
+                docs.iter()
+                    .map(|_| Value::Map(vec![(string("has_unknown_spaces"), false.into())]))
+                    .collect(),
+            ),
+        ),
+        (
+            string("span_groups"),
+            // msgpack-serialized empty list, i.e. no span groups
+            Value::Array(docs.iter().map(|_| Value::Binary(vec![0x90])).collect()),
+        ),
+    ]);
+    let mut encoder = flate2::write::ZlibEncoder::new(writer, flate2::Compression::default());
+    rmpv::encode::write_value(&mut encoder, &msg).map_err(|e| match e {
+        rmpv::encode::Error::InvalidMarkerWrite(e) | rmpv::encode::Error::InvalidDataWrite(e) => {
+            Error::Io(e)
+        }
+    })?;
+    encoder.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn murmurhash_matches_spacy() {
+        assert_eq!(string_key("coffee"), 3197928453018144401);
+        assert_eq!(string_key("PERSON"), 380);
+        assert_eq!(string_key("X"), 101);
+        assert_eq!(string_key("mark"), 423);
+        assert_eq!(string_key("_"), 456);
+        assert_eq!(string_key(""), 0);
+    }
+
+    #[test]
+    fn json_roundtrip() {
+        let doc = Doc {
+            text: "New York is big .".to_string(),
+            tokens: vec![(0, 3), (4, 8), (9, 11), (12, 15), (16, 17)],
+            entities: vec![LabeledSpan::new(0, 8, "GPE")],
+        };
+        let mut buf = vec![];
+        write_json(&mut buf, std::slice::from_ref(&doc)).unwrap();
+        assert_eq!(read_json(&buf[..]).unwrap(), [doc]);
+    }
+
+    #[test]
+    fn read_json_without_raw() {
+        let json = r#"[{"paragraphs": [{"sentences": [{"tokens": [
+            {"id": 0, "orth": "Hello", "space": ""},
+            {"id": 1, "orth": ",", "space": " "},
+            {"id": 2, "orth": "Bob", "ner": "U-PERSON"}
+        ]}]}]}]"#;
+        let docs = read_json(json.as_bytes()).unwrap();
+        assert_eq!(docs[0].text, "Hello, Bob ");
+        assert_eq!(docs[0].tokens, [(0, 5), (5, 6), (7, 10)]);
+        assert_eq!(docs[0].entities, [LabeledSpan::new(7, 10, "PERSON")]);
+    }
+
+    #[test]
+    fn docbin_fills_gaps() {
+        let doc = Doc {
+            text: "a  b\nc".to_string(),
+            tokens: vec![(0, 1), (3, 4), (5, 6)],
+            entities: vec![LabeledSpan::new(5, 6, "X")],
+        };
+        let mut buf = vec![];
+        write_docbin(&mut buf, &[doc]).unwrap();
+        let docs = read_docbin(&buf[..]).unwrap();
+        assert_eq!(docs[0].text, "a  b\nc");
+        assert_eq!(docs[0].tokens, [(0, 1), (2, 3), (3, 4), (4, 5), (5, 6)]);
+        assert_eq!(docs[0].entities, [LabeledSpan::new(5, 6, "X")]);
+    }
+
+    #[test]
+    fn docbin_roundtrip_with_symbols() {
+        let doc = Doc {
+            text: "mark X det".to_string(),
+            tokens: vec![(0, 4), (5, 6), (7, 10)],
+            entities: vec![LabeledSpan::new(5, 6, "ORG")],
+        };
+        let mut buf = vec![];
+        write_docbin(&mut buf, std::slice::from_ref(&doc)).unwrap();
+        let mut decoder = flate2::read::ZlibDecoder::new(&buf[..]);
+        let msg = rmpv::decode::read_value(&mut decoder).unwrap();
+        let tokens: Vec<u64> = map_bytes(msg.as_map().unwrap(), "tokens")
+            .unwrap()
+            .chunks_exact(8)
+            .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        for key in &[423, 101, 415, 383] {
+            assert!(tokens.contains(key), "{}", key);
+        }
+        assert_eq!(read_docbin(&buf[..]).unwrap(), [doc]);
+    }
+
+    #[test]
+    fn docbin_rejects_garbage() {
+        assert!(read_docbin(&b"not a docbin"[..]).is_err());
+    }
+
+    #[test]
+    fn docbin_rejects_bad_lengths() {
+        let doc = Doc {
+            text: "a b".to_string(),
+            tokens: vec![(0, 1), (2, 3)],
+            entities: vec![],
+        };
+        let mut buf = vec![];
+        write_docbin(&mut buf, &[doc]).unwrap();
+        let mut decoder = flate2::read::ZlibDecoder::new(&buf[..]);
+        let msg = rmpv::decode::read_value(&mut decoder).unwrap();
+        for lengths in [vec![-1i32], vec![i32::MAX, i32::MAX, i32::MAX, 2]].iter() {
+            let mut msg = msg.clone();
+            if let rmpv::Value::Map(map) = &mut msg {
+                for (k, v) in map.iter_mut() {
+                    if k.as_str() == Some("lengths") {
+                        *v = rmpv::Value::Binary(
+                            lengths.iter().flat_map(|x| x.to_le_bytes()).collect(),
+                        );
+                    }
+                }
+            }
+            let mut encoder =
+                flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+            rmpv::encode::write_value(&mut encoder, &msg).unwrap();
+            let buf = encoder.finish().unwrap();
+            assert!(matches!(
+                read_docbin(&buf[..]),
+                Err(Error::Parse { line: None, .. })
+            ));
+        }
+    }
+}
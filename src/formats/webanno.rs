@@ -0,0 +1,616 @@
+//! WebAnno TSV v3 format, as exported and imported by WebAnno and INCEpTION.
+//!
+//! Only span layers (`#T_SP=`) are read and written; columns of relation and
+//! chain layers are skipped. Offsets in TSV3 files are UTF-16 code unit
+//! offsets, which are converted from and to char offsets.
+use super::{Error, Result};
+use crate::{lift_span_index, LabeledSpan, Span};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+
+/// A span layer declared in the header, e.g.
+/// `de.tudarmstadt.ukp.dkpro.core.api.ner.type.NamedEntity` with the feature
+/// `value`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layer {
+    pub name: String,
+    pub features: Vec<String>,
+}
+
+/// An annotation on a span layer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    /// Index into [`Document::layers`].
+    pub layer: usize,
+    pub span: Span,
+    /// Value of each feature of the layer; `None` if unset.
+    pub features: Vec<Option<String>>,
+}
+
+/// A document read from a TSV3 file. All spans are char spans of `text`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Document {
+    pub text: String,
+    pub sentences: Vec<Span>,
+    pub tokens: Vec<Span>,
+    pub layers: Vec<Layer>,
+    pub annotations: Vec<Annotation>,
+}
+
+impl Document {
+    /// Returns the annotations of the layer named `layer` as spans labeled by
+    /// the value of `feature`. Annotations without a value are labeled with an
+    /// empty string.
+    pub fn labeled_spans(&self, layer: &str, feature: &str) -> Vec<LabeledSpan> {
+        let found = self.layers.iter().enumerate().find_map(|(i, l)| {
+            let f = l.features.iter().position(|f| f == feature)?;
+            Some((i, f)).filter(|_| l.name == layer)
+        });
+        let (layer, feature) = match found {
+            Some(found) => found,
+            None => return vec![],
+        };
+        self.annotations
+            .iter()
+            .filter(|a| a.layer == layer)
+            .map(|a| {
+                let label = a.features[feature].clone().unwrap_or_default();
+                LabeledSpan {
+                    span: a.span,
+                    label,
+                }
+            })
+            .collect()
+    }
+}
+
+fn unescape(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('t') => ret.push('\t'),
+                Some('n') => ret.push('\n'),
+                Some('r') => ret.push('\r'),
+                Some(c) => ret.push(c),
+                None => ret.push('\\'),
+            }
+        } else {
+            ret.push(c);
+        }
+    }
+    ret
+}
+
+fn escape(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' | '[' | ']' | '|' | '_' | '*' | ';' => {
+                ret.push('\\');
+                ret.push(c);
+            }
+            '\t' => ret.push_str("\\t"),
+            '\n' => ret.push_str("\\n"),
+            '\r' => ret.push_str("\\r"),
+            c => ret.push(c),
+        }
+    }
+    ret
+}
+
+/// Splits `s` at `sep` that is not escaped with a backslash.
+fn split_unescaped(s: &str, sep: char) -> Vec<&str> {
+    let mut ret = vec![];
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == sep {
+            ret.push(&s[start..i]);
+            start = i + c.len_utf8();
+        }
+    }
+    ret.push(&s[start..]);
+    ret
+}
+
+/// Splits a stacked cell item into its value and disambiguation ID, e.g.
+/// `PER[3]` into `("PER", Some(3))`.
+fn split_id(item: &str) -> (&str, Option<usize>) {
+    if item.ends_with(']') && !item.ends_with("\\]") {
+        if let Some(p) = item.rfind('[') {
+            if p == 0 || !item[..p].ends_with('\\') {
+                if let Ok(id) = item[p + 1..item.len() - 1].parse() {
+                    return (&item[..p], Some(id));
+                }
+            }
+        }
+    }
+    (item, None)
+}
+
+/// Converts UTF-16 offsets of `text` to char offsets.
+struct Utf16Index(HashMap<usize, usize>);
+
+impl Utf16Index {
+    fn new(text: &str) -> Self {
+        let mut map = HashMap::new();
+        let mut u = 0;
+        for (i, c) in text.chars().enumerate() {
+            map.insert(u, i);
+            u += c.len_utf16();
+        }
+        map.insert(u, text.chars().count());
+        Utf16Index(map)
+    }
+
+    fn get(&self, offset: usize, line: Option<usize>) -> Result<usize> {
+        self.0.get(&offset).copied().ok_or_else(|| Error::Parse {
+            line,
+            message: format!("offset {} is not on a char boundary of the text", offset),
+        })
+    }
+}
+
+/// A token line, with UTF-16 offsets.
+struct Row {
+    line: usize,
+    begin: usize,
+    end: usize,
+    form: String,
+    subtoken: bool,
+    cells: Vec<String>,
+}
+
+/// Reads a WebAnno TSV v3 file.
+///
+/// The text is rebuilt from the `#Text=` lines placed at the offsets of their
+/// first tokens, with gaps filled by newlines, and every token is checked
+/// against it.
+///
+/// # Examples
+///
+/// ```
+/// use textspan::LabeledSpan;
+/// let tsv = "#FORMAT=WebAnno TSV 3.3\n\
+///            #T_SP=de.tudarmstadt.ukp.dkpro.core.api.ner.type.NamedEntity|value\n\n\n\
+///            #Text=Barack Obama visited Paris .\n\
+///            1-1\t0-6\tBarack\tPER[1]\n\
+///            1-2\t7-12\tObama\tPER[1]\n\
+///            1-3\t13-20\tvisited\t_\n\
+///            1-4\t21-26\tParis\tLOC\n\
+///            1-5\t27-28\t.\t_\n";
+/// let doc = textspan::formats::webanno::read_tsv3(tsv.as_bytes()).unwrap();
+/// assert_eq!(
+///     doc.labeled_spans("de.tudarmstadt.ukp.dkpro.core.api.ner.type.NamedEntity", "value"),
+///     [LabeledSpan::new(0, 12, "PER"), LabeledSpan::new(21, 26, "LOC")]
+/// );
+/// ```
+pub fn read_tsv3<R: Read>(reader: R) -> Result<Document> {
+    let mut doc = Document::default();
+    // number of columns of each layer, and whether it is a span layer
+    let mut columns: Vec<(usize, bool)> = vec![];
+    let mut sentences: Vec<(Vec<String>, Vec<Row>)> = vec![];
+    let mut in_text = false;
+    for (i, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        let lineno = i + 1;
+        let err = |message: String| Error::Parse {
+            line: Some(lineno),
+            message,
+        };
+        if let Some(header) = line.strip_prefix("#FORMAT=") {
+            if !header.starts_with("WebAnno TSV 3") {
+                return Err(err(format!("unsupported format {:?}", header)));
+            }
+        } else if let Some(decl) = line.strip_prefix("#T_SP=") {
+            let mut parts = decl.split('|');
+            let name = parts.next().unwrap_or_default().to_string();
+            let features: Vec<String> = parts.map(|s| s.to_string()).collect();
+            columns.push((features.len().max(1), true));
+            doc.layers.push(Layer { name, features });
+        } else if let Some(decl) = line
+            .strip_prefix("#T_RL=")
+            .or_else(|| line.strip_prefix("#T_CH="))
+        {
+            columns.push((decl.split('|').count() - 1, false));
+        } else if let Some(text) = line.strip_prefix("#Text=") {
+            if in_text {
+                let (texts, _) = sentences.last_mut().unwrap();
+                texts.push(unescape(text));
+            } else {
+                sentences.push((vec![unescape(text)], vec![]));
+            }
+            in_text = true;
+        } else if line.trim().is_empty() || line.starts_with('#') {
+            in_text = false;
+        } else {
+            in_text = false;
+            let cells: Vec<&str> = line.split('\t').collect();
+            let expected = 3 + columns.iter().map(|c| c.0).sum::<usize>();
+            if cells.len() < 3 || cells.len() < expected {
+                return Err(err(format!(
+                    "expected {} columns, found {}",
+                    expected,
+                    cells.len()
+                )));
+            }
+            let (begin, end) = cells[1]
+                .split_once('-')
+                .and_then(|(l, r)| Some((l.parse().ok()?, r.parse().ok()?)))
+                .ok_or_else(|| err(format!("invalid offsets {:?}", cells[1])))?;
+            let row = Row {
+                line: lineno,
+                begin,
+                end,
+                form: unescape(cells[2]),
+                subtoken: cells[0].contains('.'),
+                cells: cells[3..].iter().map(|s| s.to_string()).collect(),
+            };
+            match sentences.last_mut() {
+                Some((_, rows)) => rows.push(row),
+                None => return Err(err("token before any `#Text=` line".to_string())),
+            }
+        }
+    }
+
+    // rebuild the text in UTF-16 units
+    let mut cursor = 0;
+    let mut sentence_offsets = vec![];
+    for (texts, rows) in &sentences {
+        let begin = rows.first().map_or(cursor, |r| r.begin);
+        if begin < cursor {
+            return Err(Error::Parse {
+                line: rows.first().map(|r| r.line),
+                message: "sentences overlap".to_string(),
+            });
+        }
+        while cursor < begin {
+            doc.text.push('\n');
+            cursor += 1;
+        }
+        let text = texts.join("\n");
+        let len: usize = text.chars().map(char::len_utf16).sum();
+        sentence_offsets.push((begin, begin + len, rows.first().map(|r| r.line)));
+        doc.text.push_str(&text);
+        cursor += len;
+    }
+    let index = Utf16Index::new(&doc.text);
+    let chars: Vec<char> = doc.text.chars().collect();
+    for &(l, r, line) in &sentence_offsets {
+        doc.sentences
+            .push((index.get(l, line)?, index.get(r, line)?));
+    }
+
+    // (layer, disambiguation ID) -> index in `doc.annotations`
+    let mut ids: HashMap<(usize, usize), usize> = HashMap::new();
+    for row in sentences.iter().flat_map(|(_, rows)| rows) {
+        let span = (
+            index.get(row.begin, Some(row.line))?,
+            index.get(row.end, Some(row.line))?,
+        );
+        if chars[span.0..span.1.max(span.0)].iter().collect::<String>() != row.form {
+            return Err(Error::InvalidSpan {
+                line: Some(row.line),
+                span,
+                message: format!("token {:?} does not match the text", row.form),
+            });
+        }
+        if !row.subtoken {
+            doc.tokens.push(span);
+        }
+        let mut col = 0;
+        let mut layer = 0;
+        for &(n, is_span) in &columns {
+            if !is_span {
+                col += n;
+                continue;
+            }
+            let stacks: Vec<Vec<&str>> = row.cells[col..col + n]
+                .iter()
+                .map(|cell| split_unescaped(cell, '|'))
+                .collect();
+            for k in 0..stacks[0].len() {
+                if stacks[0][k] == "_" {
+                    continue;
+                }
+                let mut id = None;
+                let mut features = vec![];
+                for stack in &stacks {
+                    let (value, i) = split_id(stack.get(k).copied().unwrap_or("_"));
+                    id = id.or(i);
+                    features.push(match value {
+                        "*" | "_" => None,
+                        v => Some(unescape(v)),
+                    });
+                }
+                features.truncate(doc.layers[layer].features.len());
+                match id.and_then(|id| ids.get(&(layer, id))) {
+                    Some(&a) => doc.annotations[a].span.1 = span.1,
+                    None => {
+                        if let Some(id) = id {
+                            ids.insert((layer, id), doc.annotations.len());
+                        }
+                        doc.annotations.push(Annotation {
+                            layer,
+                            span,
+                            features,
+                        });
+                    }
+                }
+            }
+            col += n;
+            layer += 1;
+        }
+    }
+    Ok(doc)
+}
+
+/// Writes `doc` as a WebAnno TSV 3.3 file.
+///
+/// Every annotation must start and end at token boundaries and refer to one of
+/// `doc.layers`, and every token must lie within a sentence. Otherwise
+/// [`Error::InvalidSpan`] is returned.
+pub fn write_tsv3<W: Write>(mut writer: W, doc: &Document) -> Result<()> {
+    let chars: Vec<char> = doc.text.chars().collect();
+    let mut utf16 = Vec::with_capacity(chars.len() + 1);
+    let mut u = 0;
+    for c in &chars {
+        utf16.push(u);
+        u += c.len_utf16();
+    }
+    utf16.push(u);
+
+    let len = chars.len();
+    for &span in doc.sentences.iter().chain(&doc.tokens) {
+        if span.0 > span.1 || span.1 > len {
+            return Err(Error::InvalidSpan {
+                line: None,
+                span,
+                message: format!("out of the text of length {}", len),
+            });
+        }
+    }
+
+    // cells[token][layer] holds the indices of the stacked annotations
+    let mut cells: Vec<Vec<Vec<usize>>> = vec![vec![vec![]; doc.layers.len()]; doc.tokens.len()];
+    let mut ranges = Vec::with_capacity(doc.annotations.len());
+    for (a, annotation) in doc.annotations.iter().enumerate() {
+        if annotation.layer >= doc.layers.len() {
+            return Err(Error::InvalidSpan {
+                line: None,
+                span: annotation.span,
+                message: format!("annotation has unknown layer {}", annotation.layer),
+            });
+        }
+        let (l, r) = match lift_span_index(annotation.span, &doc.tokens) {
+            (Ok(l), Ok(r)) if l < r => (l, r),
+            _ => {
+                return Err(Error::InvalidSpan {
+                    line: None,
+                    span: annotation.span,
+                    message: "annotation does not match token boundaries".to_string(),
+                })
+            }
+        };
+        for token in &mut cells[l..r] {
+            token[annotation.layer].push(a);
+        }
+        ranges.push((l, r));
+    }
+    // multi-token and stacked annotations need a disambiguation ID
+    let mut next_id = 1;
+    let ids: Vec<Option<usize>> = doc
+        .annotations
+        .iter()
+        .zip(&ranges)
+        .map(|(annotation, &(l, r))| {
+            let stacked = cells[l..r]
+                .iter()
+                .any(|token| token[annotation.layer].len() > 1);
+            if r - l > 1 || stacked {
+                next_id += 1;
+                Some(next_id - 1)
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    writeln!(writer, "#FORMAT=WebAnno TSV 3.3")?;
+    for layer in &doc.layers {
+        write!(writer, "#T_SP={}", layer.name)?;
+        for feature in &layer.features {
+            write!(writer, "|{}", feature)?;
+        }
+        writeln!(writer)?;
+    }
+    writeln!(writer)?;
+    let mut t = 0;
+    for (s, &(start, end)) in doc.sentences.iter().enumerate() {
+        let text: String = chars[start..end].iter().collect();
+        writeln!(writer)?;
+        for line in text.split('\n') {
+            writeln!(writer, "#Text={}", escape(line))?;
+        }
+        let mut n = 0;
+        while t < doc.tokens.len() && doc.tokens[t].1 <= end {
+            let (l, r) = doc.tokens[t];
+            if l < start {
+                return Err(Error::InvalidSpan {
+                    line: None,
+                    span: (l, r),
+                    message: "token is not within a sentence".to_string(),
+                });
+            }
+            n += 1;
+            let form: String = chars[l..r].iter().collect();
+            write!(
+                writer,
+                "{}-{}\t{}-{}\t{}",
+                s + 1,
+                n,
+                utf16[l],
+                utf16[r],
+                escape(&form)
+            )?;
+            for (layer, items) in doc.layers.iter().zip(&cells[t]) {
+                for f in 0..layer.features.len().max(1) {
+                    let values: Vec<String> = items
+                        .iter()
+                        .map(|&a| {
+                            let value = doc.annotations[a]
+                                .features
+                                .get(f)
+                                .and_then(|v| v.as_deref())
+                                .map_or_else(|| "*".to_string(), escape);
+                            match ids[a] {
+                                Some(id) => format!("{}[{}]", value, id),
+                                None => value,
+                            }
+                        })
+                        .collect();
+                    if values.is_empty() {
+                        write!(writer, "\t_")?;
+                    } else {
+                        write!(writer, "\t{}", values.join("|"))?;
+                    }
+                }
+            }
+            writeln!(writer)?;
+            t += 1;
+        }
+    }
+    if t < doc.tokens.len() {
+        return Err(Error::InvalidSpan {
+            line: None,
+            span: doc.tokens[t],
+            message: "token is not within a sentence".to_string(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NE: &str = "de.tudarmstadt.ukp.dkpro.core.api.ner.type.NamedEntity";
+
+    #[test]
+    fn read_tsv3_stacked_and_utf16() {
+        let tsv = "#FORMAT=WebAnno TSV 3.3\n\
+                   #T_SP=de.tudarmstadt.ukp.dkpro.core.api.ner.type.NamedEntity|identifier|value\n\
+                   #T_RL=webanno.custom.Rel|label|BT_webanno.custom.Span\n\n\n\
+                   #Text=😀 New York\n\
+                   1-1\t0-2\t😀\t_\t_\t_\t_\n\
+                   1-2\t3-6\tNew\t*[1]|*[2]\tGPE[1]|LOC[2]\t_\t_\n\
+                   1-3\t7-11\tYork\t*[1]\tGPE[1]\t_\t_\n\n\
+                   #Text=Line one\n\
+                   #Text=a\\|b\n\
+                   2-1\t13-17\tLine\t*\t\\_x\t_\t_\n\
+                   2-2\t22-25\ta|b\t_\t_\t_\t_\n";
+        let doc = read_tsv3(tsv.as_bytes()).unwrap();
+        assert_eq!(doc.text, "😀 New York\n\nLine one\na|b");
+        assert_eq!(doc.tokens, [(0, 1), (2, 5), (6, 10), (12, 16), (21, 24)]);
+        assert_eq!(doc.sentences, [(0, 10), (12, 24)]);
+        assert_eq!(
+            doc.labeled_spans(NE, "value"),
+            [
+                LabeledSpan::new(2, 10, "GPE"),
+                LabeledSpan::new(2, 5, "LOC"),
+                LabeledSpan::new(12, 16, "_x"),
+            ]
+        );
+        assert_eq!(doc.annotations[0].features, [None, Some("GPE".to_string())]);
+        assert!(doc.labeled_spans(NE, "unknown").is_empty());
+    }
+
+    #[test]
+    fn tsv3_roundtrip() {
+        let doc = Document {
+            text: "Tōkyō is big.\nYes".to_string(),
+            sentences: vec![(0, 13), (14, 17)],
+            tokens: vec![(0, 5), (6, 8), (9, 12), (12, 13), (14, 17)],
+            layers: vec![Layer {
+                name: NE.to_string(),
+                features: vec!["value".to_string()],
+            }],
+            annotations: vec![
+                Annotation {
+                    layer: 0,
+                    span: (0, 5),
+                    features: vec![Some("LOC".to_string())],
+                },
+                Annotation {
+                    layer: 0,
+                    span: (0, 12),
+                    features: vec![None],
+                },
+                Annotation {
+                    layer: 0,
+                    span: (14, 17),
+                    features: vec![Some("a|b".to_string())],
+                },
+            ],
+        };
+        let mut buf = vec![];
+        write_tsv3(&mut buf, &doc).unwrap();
+        assert_eq!(read_tsv3(&buf[..]).unwrap(), doc);
+    }
+
+    #[test]
+    fn read_tsv3_errors() {
+        let tsv = "#FORMAT=WebAnno TSV 3.3\n#Text=foo\n1-1\t0-3\tbar\n";
+        assert!(matches!(
+            read_tsv3(tsv.as_bytes()),
+            Err(Error::InvalidSpan {
+                line: Some(3),
+                span: (0, 3),
+                ..
+            })
+        ));
+        let tsv = "#FORMAT=WebAnno TSV 3.3\n#T_SP=X|v\n#Text=foo\n1-1\t0-3\tfoo\n";
+        assert!(matches!(
+            read_tsv3(tsv.as_bytes()),
+            Err(Error::Parse { line: Some(4), .. })
+        ));
+    }
+
+    #[test]
+    fn write_tsv3_errors() {
+        let doc = |sentences, tokens, layer| Document {
+            text: "ab".to_string(),
+            sentences,
+            tokens,
+            layers: vec![Layer {
+                name: NE.to_string(),
+                features: vec!["value".to_string()],
+            }],
+            annotations: vec![Annotation {
+                layer,
+                span: (0, 2),
+                features: vec![None],
+            }],
+        };
+        let write = |doc: Document| write_tsv3(vec![], &doc);
+        assert!(matches!(
+            write(doc(vec![(0, 5)], vec![(0, 2)], 0)),
+            Err(Error::InvalidSpan { span: (0, 5), .. })
+        ));
+        assert!(matches!(
+            write(doc(vec![(0, 2)], vec![(0, 3)], 0)),
+            Err(Error::InvalidSpan { span: (0, 3), .. })
+        ));
+        assert!(matches!(
+            write(doc(vec![(0, 2)], vec![(0, 2)], 1)),
+            Err(Error::InvalidSpan { span: (0, 2), .. })
+        ));
+        assert!(write(doc(vec![(0, 2)], vec![(0, 2)], 0)).is_ok());
+    }
+}
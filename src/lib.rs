@@ -7,8 +7,46 @@ extern crate quickcheck_macros;
 use std::borrow::Borrow;
 use std::convert::AsRef;
 
+pub mod agreement;
+#[cfg(feature = "rayon")]
+mod batch;
+mod error;
+pub mod eval;
+pub mod formats;
+pub mod mapping;
+#[cfg(feature = "markdown")]
+pub mod markdown;
+pub mod markup;
+pub mod matching;
+pub mod render;
+#[cfg(feature = "regex")]
+pub mod replace;
+pub mod tokenize;
+pub mod visualize;
+
+#[cfg(feature = "rayon")]
+pub use batch::{align_spans_batch, get_original_spans_batch, try_align_spans_batch};
+pub use error::Error;
+pub use mapping::MappedText;
+
 pub type Span = (usize, usize);
 
+/// A span with a label, e.g. a named entity.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LabeledSpan {
+    pub span: Span,
+    pub label: String,
+}
+
+impl LabeledSpan {
+    pub fn new(start: usize, end: usize, label: impl Into<String>) -> Self {
+        LabeledSpan {
+            span: (start, end),
+            label: label.into(),
+        }
+    }
+}
+
 fn get_span_indices<S: Borrow<str>>(tokens: &[S]) -> Vec<Span> {
     tokens
         .iter()
@@ -62,6 +100,28 @@ pub fn align_spans(spans: &[Span], text: &str, original_text: &str) -> Vec<Vec<S
     align_spans_by_mapping(spans, &mapping)
 }
 
+/// Same as [`align_spans`], but returns an error if a span is inverted or out
+/// of `text`.
+///
+/// # Examples
+///
+/// ```
+/// use textspan::{try_align_spans, Error};
+/// assert_eq!(try_align_spans(&[(0, 3)], "foo", "FOO"), Ok(vec![vec![(0, 3)]]));
+/// assert_eq!(
+///     try_align_spans(&[(0, 4)], "foo", "FOO"),
+///     Err(Error::OutOfBounds { span: (0, 4), len: 3 })
+/// );
+/// ```
+pub fn try_align_spans(
+    spans: &[Span],
+    text: &str,
+    original_text: &str,
+) -> Result<Vec<Vec<Span>>, Error> {
+    error::check_spans(spans, Some(text.chars().count()))?;
+    Ok(align_spans(spans, text, original_text))
+}
+
 /// Converts the spans by the given `mapping`.
 /// Generally speaking, the character correspondence between two texts is not
 /// necessarily surjective, not injective, not even a methematical map - some
@@ -109,6 +169,28 @@ pub fn align_spans_by_mapping<T: AsRef<[usize]>>(spans: &[Span], mapping: &[T])
     ret
 }
 
+/// Same as [`align_spans_by_mapping`], but returns an error if a span is
+/// inverted or out of `mapping`, instead of clamping it.
+///
+/// # Examples
+///
+/// ```
+/// use textspan::{try_align_spans_by_mapping, Error};
+/// let mapping = [vec![0, 1], vec![], vec![2], vec![4, 5, 6]];
+/// assert_eq!(try_align_spans_by_mapping(&[(0, 2)], &mapping), Ok(vec![vec![(0, 2)]]));
+/// assert_eq!(
+///     try_align_spans_by_mapping(&[(3, 2)], &mapping),
+///     Err(Error::InvertedSpan { span: (3, 2) })
+/// );
+/// ```
+pub fn try_align_spans_by_mapping<T: AsRef<[usize]>>(
+    spans: &[Span],
+    mapping: &[T],
+) -> Result<Vec<Vec<Span>>, Error> {
+    error::check_spans(spans, Some(mapping.len()))?;
+    Ok(align_spans_by_mapping(spans, mapping))
+}
+
 /// Remove overlapping spans from given `spans`.
 /// First, longest spans are remained - if the two spans are overlapped, the
 /// first span will be remained. If the two spans are overlapped and their start
@@ -137,6 +219,13 @@ pub fn remove_span_overlaps(spans: &[Span]) -> Vec<Span> {
     ret
 }
 
+/// Same as [`remove_span_overlaps`], but returns an error if a span is
+/// inverted.
+pub fn try_remove_span_overlaps(spans: &[Span]) -> Result<Vec<Span>, Error> {
+    error::check_spans(spans, None)?;
+    Ok(remove_span_overlaps(spans))
+}
+
 /// Remove overlapping spans from given `spans`, and returns remained span indices.
 /// First, longest spans are remained - if the two spans are overlapped, the
 /// first span will be remained. If the two spans are overlapped and their start
@@ -169,6 +258,13 @@ pub fn remove_span_overlaps_idx(spans: &[Span]) -> Vec<usize> {
     ret
 }
 
+/// Same as [`remove_span_overlaps_idx`], but returns an error if a span is
+/// inverted.
+pub fn try_remove_span_overlaps_idx(spans: &[Span]) -> Result<Vec<usize>, Error> {
+    error::check_spans(spans, None)?;
+    Ok(remove_span_overlaps_idx(spans))
+}
+
 /// Convert `span` indices to `target_spans` based indices.
 /// Expects `target_spans` is sorted and not overlapping.
 ///
@@ -251,6 +347,18 @@ pub fn lift_span_index(
     (li, ri)
 }
 
+/// Same as [`lift_span_index`], but returns an error if `span` or one of
+/// `target_spans` is inverted.
+#[allow(clippy::type_complexity)]
+pub fn try_lift_span_index(
+    span: Span,
+    target_spans: &[Span],
+) -> Result<(Result<usize, usize>, Result<usize, usize>), Error> {
+    error::check_span(span, None)?;
+    error::check_spans(target_spans, None)?;
+    Ok(lift_span_index(span, target_spans))
+}
+
 /// Convert `spans` indices on `target_spans`
 ///
 /// # Example
@@ -292,6 +400,18 @@ pub fn lift_spans_index(
     ret
 }
 
+/// Same as [`lift_spans_index`], but returns an error if one of `spans` or
+/// `target_spans` is inverted.
+#[allow(clippy::type_complexity)]
+pub fn try_lift_spans_index(
+    spans: &[Span],
+    target_spans: &[Span],
+) -> Result<Vec<(Result<usize, usize>, Result<usize, usize>)>, Error> {
+    error::check_spans(spans, None)?;
+    error::check_spans(target_spans, None)?;
+    Ok(lift_spans_index(spans, target_spans))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -344,6 +464,7 @@ mod tests {
         assert_eq!(new_spans, new_spans2);
     }
     #[test]
+    #[allow(clippy::useless_vec)]
     fn align_spans_handmade() {
         for (case, expected) in vec![
             ((vec![], "", ""), vec![]),
@@ -362,6 +483,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::useless_vec)]
     fn align_spans_by_mapping_handmade() {
         for (case, expected) in vec![
             ((vec![], vec![]), vec![]),
@@ -416,8 +538,10 @@ mod tests {
             assert_eq!(ret[0], vec![])
         } else {
             if ret[0].is_empty() {
-                assert_eq!(mapping[start], vec![]);
-                assert_eq!(mapping[end - 1], vec![]);
+                // `vec![]` alone is ambiguous once serde_json is linked
+                let empty: Vec<usize> = vec![];
+                assert_eq!(mapping[start], empty);
+                assert_eq!(mapping[end - 1], empty);
                 return;
             }
             let mut cur = None;
@@ -518,4 +642,48 @@ mod tests {
             input
         );
     }
+
+    #[test]
+    fn try_functions_reject_invalid_spans() {
+        assert_eq!(
+            try_align_spans(&[(2, 1)], "foo", "foo"),
+            Err(Error::InvertedSpan { span: (2, 1) })
+        );
+        assert_eq!(
+            try_align_spans(&[(0, 3)], "東京", "東京"),
+            Err(Error::OutOfBounds {
+                span: (0, 3),
+                len: 2
+            })
+        );
+        assert_eq!(
+            try_align_spans_by_mapping(&[(1, 3)], &[vec![0], vec![1]]),
+            Err(Error::OutOfBounds {
+                span: (1, 3),
+                len: 2
+            })
+        );
+        assert!(try_remove_span_overlaps(&[(0, 1), (3, 2)]).is_err());
+        assert!(try_remove_span_overlaps_idx(&[(3, 2)]).is_err());
+        assert!(try_lift_span_index((0, 1), &[(1, 0)]).is_err());
+        assert!(try_lift_spans_index(&[(1, 0)], &[(0, 1)]).is_err());
+    }
+
+    #[quickcheck]
+    fn try_functions_agree_on_valid_spans(spans: Vec<Span>, text: String) {
+        let len = text.chars().count();
+        let spans: Vec<_> = spans
+            .into_iter()
+            .map(|(l, r)| (l.min(r) % (len + 1), l.max(r) % (len + 1)))
+            .filter(|&(l, r)| l <= r)
+            .collect();
+        assert_eq!(
+            try_align_spans(&spans, &text, &text),
+            Ok(align_spans(&spans, &text, &text))
+        );
+        assert_eq!(
+            try_remove_span_overlaps_idx(&spans),
+            Ok(remove_span_overlaps_idx(&spans))
+        );
+    }
 }
@@ -0,0 +1,151 @@
+//! Texts derived from a source text, together with their character
+//! correspondence to it.
+use crate::error::{check_byte_span, check_spans};
+use crate::{align_spans_by_mapping, Error, Span};
+
+/// A text obtained by transforming a source text, e.g. with
+/// [`replace_all`](crate::replace::replace_all).
+///
+/// `mapping[i]` holds the char indices in the source text that the `i`-th char
+/// of `text` was produced from, and `byte_mapping` holds the same
+/// correspondence on byte offsets. Both can be passed directly to
+/// [`align_spans_by_mapping`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MappedText {
+    pub text: String,
+    pub mapping: Vec<Vec<usize>>,
+    pub byte_mapping: Vec<Vec<usize>>,
+}
+
+impl MappedText {
+    /// Converts char spans in `self.text` to char spans in the source text.
+    pub fn align_spans(&self, spans: &[Span]) -> Vec<Vec<Span>> {
+        align_spans_by_mapping(spans, &self.mapping)
+    }
+
+    /// Converts byte spans in `self.text` to byte spans in the source text.
+    pub fn align_byte_spans(&self, spans: &[Span]) -> Vec<Vec<Span>> {
+        align_spans_by_mapping(spans, &self.byte_mapping)
+    }
+
+    /// Same as [`MappedText::align_spans`], but returns an error if a span is
+    /// inverted or out of `self.text`.
+    pub fn try_align_spans(&self, spans: &[Span]) -> Result<Vec<Vec<Span>>, Error> {
+        check_spans(spans, Some(self.mapping.len()))?;
+        Ok(self.align_spans(spans))
+    }
+
+    /// Same as [`MappedText::align_byte_spans`], but returns an error if a
+    /// span is inverted, out of `self.text` or not on char boundaries.
+    pub fn try_align_byte_spans(&self, spans: &[Span]) -> Result<Vec<Vec<Span>>, Error> {
+        for &span in spans {
+            check_byte_span(span, &self.text)?;
+        }
+        Ok(self.align_byte_spans(spans))
+    }
+}
+
+/// Incrementally builds a [`MappedText`] from pieces of a source text.
+///
+/// Source positions are given as byte offsets, which is what parsers and
+/// `regex` naturally produce; they must lie on char boundaries and be pushed
+/// in ascending order.
+pub(crate) struct MappedTextBuilder<'a> {
+    source: &'a str,
+    /// `char_index[b]` is the char index of the char starting at byte `b`;
+    /// one extra entry for `source.len()`.
+    char_index: Vec<usize>,
+    text: String,
+    mapping: Vec<Vec<usize>>,
+}
+
+impl<'a> MappedTextBuilder<'a> {
+    pub(crate) fn new(source: &'a str) -> Self {
+        let mut char_index = vec![0; source.len() + 1];
+        let mut n = 0;
+        for (b, _) in source.char_indices() {
+            char_index[b] = n;
+            n += 1;
+        }
+        char_index[source.len()] = n;
+        MappedTextBuilder {
+            source,
+            char_index,
+            text: String::new(),
+            mapping: vec![],
+        }
+    }
+
+    /// Copies `source[start..end]` verbatim, mapping each char to itself.
+    pub(crate) fn copy(&mut self, start: usize, end: usize) {
+        let offset = self.char_index[start];
+        for (i, c) in self.source[start..end].chars().enumerate() {
+            self.text.push(c);
+            self.mapping.push(vec![offset + i]);
+        }
+    }
+
+    /// Appends `s`, mapping every char of it to all chars in
+    /// `source[start..end]`.
+    pub(crate) fn push_str(&mut self, s: &str, start: usize, end: usize) {
+        let (l, r) = (self.char_index[start], self.char_index[end]);
+        for c in s.chars() {
+            self.text.push(c);
+            self.mapping.push((l..r).collect());
+        }
+    }
+
+    /// Appends `s`, aligning it to `source[start..end]` with
+    /// [`tokenizations::get_charmap`]. Chars of `s` that cannot be aligned map
+    /// to the whole range.
+    #[cfg(feature = "markdown")]
+    pub(crate) fn push_aligned(&mut self, s: &str, start: usize, end: usize) {
+        let (mapping, _) = tokenizations::get_charmap(s, &self.source[start..end]);
+        let (l, r) = (self.char_index[start], self.char_index[end]);
+        for (c, chars) in s.chars().zip(mapping) {
+            self.text.push(c);
+            if chars.is_empty() {
+                self.mapping.push((l..r).collect());
+            } else {
+                self.mapping
+                    .push(chars.into_iter().map(|i| l + i).collect());
+            }
+        }
+    }
+
+    /// Appends `c` without any correspondence in the source text.
+    pub(crate) fn insert(&mut self, c: char) {
+        self.text.push(c);
+        self.mapping.push(vec![]);
+    }
+
+    pub(crate) fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub(crate) fn finish(self) -> MappedText {
+        let source_offsets: Vec<_> = self
+            .source
+            .char_indices()
+            .map(|(b, c)| (b, b + c.len_utf8()))
+            .collect();
+        let mut byte_mapping = Vec::with_capacity(self.text.len());
+        for (c, chars) in self.text.chars().zip(&self.mapping) {
+            let bytes: Vec<_> = chars
+                .iter()
+                .flat_map(|&i| {
+                    let (l, r) = source_offsets[i];
+                    l..r
+                })
+                .collect();
+            for _ in 0..c.len_utf8() {
+                byte_mapping.push(bytes.clone());
+            }
+        }
+        MappedText {
+            text: self.text,
+            mapping: self.mapping,
+            byte_mapping,
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! Plain text extraction from Markdown, keeping the offsets into the Markdown
+//! source.
+//!
+//! Requires the `markdown` feature.
+use crate::mapping::{MappedText, MappedTextBuilder};
+use pulldown_cmark::{Event, Options, Parser, TagEnd};
+
+/// Renders `source` as plain text and returns it along with its mapping to
+/// `source`.
+///
+/// Emphasis markers, link destinations, list markers, raw HTML and the
+/// backticks of code spans are dropped. Block elements are separated by a
+/// newline, which has no correspondence in the source unless it comes from a
+/// line break inside a paragraph.
+///
+/// # Examples
+///
+/// ```
+/// let md = "# Title\n\nSee **[the docs](https://example.com)** and `foo()`.";
+/// let rendered = textspan::markdown::markdown_to_text(md);
+/// assert_eq!(rendered.text, "Title\nSee the docs and foo().\n");
+/// // "the docs"
+/// assert_eq!(rendered.align_spans(&[(10, 18)]), [[(16, 24)]]);
+/// // "foo()"
+/// assert_eq!(rendered.align_spans(&[(23, 28)]), [[(54, 59)]]);
+/// ```
+pub fn markdown_to_text(source: &str) -> MappedText {
+    let mut builder = MappedTextBuilder::new(source);
+    let parser = Parser::new_ext(source, Options::all());
+    for (event, range) in parser.into_offset_iter() {
+        match event {
+            // a lone char decoded from an entity reference or an escape
+            // corresponds to the whole construct
+            Event::Text(text) if text.chars().count() == 1 && *text != source[range.clone()] => {
+                builder.push_str(&text, range.start, range.end)
+            }
+            Event::Text(text)
+            | Event::Code(text)
+            | Event::InlineMath(text)
+            | Event::DisplayMath(text) => builder.push_aligned(&text, range.start, range.end),
+            Event::SoftBreak | Event::HardBreak => {
+                builder.push_aligned("\n", range.start, range.end)
+            }
+            Event::End(TagEnd::TableCell) => builder.insert('\t'),
+            Event::End(TagEnd::Paragraph)
+            | Event::End(TagEnd::Heading(_))
+            | Event::End(TagEnd::CodeBlock)
+            | Event::End(TagEnd::Item)
+            | Event::End(TagEnd::TableHead)
+            | Event::End(TagEnd::TableRow)
+            | Event::Rule => {
+                let text = builder.text();
+                if !text.is_empty() && !text.ends_with('\n') {
+                    builder.insert('\n');
+                }
+            }
+            _ => (),
+        }
+    }
+    builder.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest(
+        input,
+        expected,
+        case("", ""),
+        case("plain", "plain\n"),
+        case("*a* __b__ ~~c~~", "a b c\n"),
+        case("- foo\n- bar\n\n1. baz", "foo\nbar\nbaz\n"),
+        case("> quoted\n> text", "quoted\ntext\n"),
+        case("```rust\nlet x;\n```", "let x;\n"),
+        case("a\\*b &amp; <span>c</span>", "a*b & c\n"),
+        case("![alt text](img.png)", "alt text\n"),
+        case("| a | b |\n|---|---|\n| c | d |", "a\tb\t\nc\td\t\n")
+    )]
+    fn markdown_to_text_handmade(input: &str, expected: &str) {
+        let ret = markdown_to_text(input);
+        assert_eq!(ret.text, expected);
+        assert_eq!(ret.mapping.len(), expected.chars().count());
+    }
+
+    #[test]
+    fn markdown_to_text_mapping() {
+        let source = "a\\*b &amp; `é`";
+        let ret = markdown_to_text(source);
+        assert_eq!(ret.text, "a*b & é\n");
+        assert_eq!(ret.mapping[0], [0]);
+        assert_eq!(ret.mapping[1], [2]);
+        assert_eq!(ret.mapping[4], [5, 6, 7, 8, 9]);
+        assert_eq!(ret.mapping[6], [12]);
+        assert_eq!(ret.mapping[7], Vec::<usize>::new());
+        assert_eq!(ret.align_byte_spans(&[(6, 8)]), [[(12, 14)]]);
+    }
+
+    #[test]
+    fn markdown_to_text_soft_break() {
+        let ret = markdown_to_text("foo\nbar");
+        assert_eq!(ret.text, "foo\nbar\n");
+        assert_eq!(ret.mapping[3], [3]);
+    }
+}
@@ -0,0 +1,355 @@
+//! Plain text extraction from HTML and XML, keeping the offsets into the
+//! markup.
+use crate::mapping::{MappedText, MappedTextBuilder};
+use std::borrow::Cow;
+
+mod entities;
+
+/// Options for [`strip_markup_with_options`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StripOptions {
+    /// Inserts a newline at the boundaries of HTML block elements (`<p>`,
+    /// `<div>`, `<li>`, `<br>`, ...) so that paragraphs are not glued
+    /// together. Inserted newlines have no correspondence in the source.
+    pub block_newlines: bool,
+}
+
+/// Strips tags, comments and processing instructions from `source` and
+/// decodes entity references, returning the plain text along with its mapping
+/// to `source`.
+///
+/// Entity references are decoded as HTML5 does in text: all named references
+/// of HTML5 are recognized, and the legacy ones such as `&amp` also without
+/// the trailing `;`.
+///
+/// A decoded entity reference maps to the whole reference, e.g. `&` produced
+/// from `&amp;` maps to all 5 bytes of it. The contents of `<script>` and
+/// `<style>` are dropped and CDATA sections are copied verbatim.
+///
+/// # Examples
+///
+/// ```
+/// let html = "<p>Tom &amp; Jerry</p>";
+/// let stripped = textspan::markup::strip_markup(html);
+/// assert_eq!(stripped.text, "Tom & Jerry");
+/// assert_eq!(stripped.align_byte_spans(&[(4, 11)]), [[(7, 18)]]);
+/// ```
+pub fn strip_markup(source: &str) -> MappedText {
+    strip_markup_with_options(source, &StripOptions::default())
+}
+
+/// Same as [`strip_markup`], with the given `options`.
+///
+/// # Examples
+///
+/// ```
+/// use textspan::markup::{strip_markup_with_options, StripOptions};
+/// let options = StripOptions { block_newlines: true };
+/// let stripped = strip_markup_with_options("<p>foo</p><p>bar</p>", &options);
+/// assert_eq!(stripped.text, "foo\nbar\n");
+/// assert_eq!(stripped.align_byte_spans(&[(4, 7)]), [[(13, 16)]]);
+/// ```
+pub fn strip_markup_with_options(source: &str, options: &StripOptions) -> MappedText {
+    let bytes = source.as_bytes();
+    let mut builder = MappedTextBuilder::new(source);
+    let mut last = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'<' => {
+                let markup = match parse_markup(source, i) {
+                    Some(markup) => markup,
+                    None => {
+                        i += 1;
+                        continue;
+                    }
+                };
+                builder.copy(last, i);
+                match markup {
+                    Markup::Skip(end) => i = end,
+                    Markup::CData(start, end, next) => {
+                        builder.copy(start, end);
+                        i = next;
+                    }
+                    Markup::Tag { name, close, end } => {
+                        i = end;
+                        if !close && (name == "script" || name == "style") {
+                            i = find_closing_tag(source, i, &name);
+                        }
+                        if options.block_newlines && is_block_element(&name) {
+                            let text = builder.text();
+                            if !text.is_empty() && !text.ends_with('\n') {
+                                builder.insert('\n');
+                            }
+                        }
+                    }
+                }
+                last = i;
+            }
+            b'&' => match parse_entity(source, i) {
+                Some((s, end)) => {
+                    builder.copy(last, i);
+                    builder.push_str(&s, i, end);
+                    i = end;
+                    last = i;
+                }
+                None => i += 1,
+            },
+            _ => i += 1,
+        }
+    }
+    builder.copy(last, source.len());
+    builder.finish()
+}
+
+enum Markup {
+    /// Comments, doctypes and processing instructions, ending at the given
+    /// byte offset.
+    Skip(usize),
+    /// `<![CDATA[...]]>`: content start, content end and the end of section.
+    CData(usize, usize, usize),
+    Tag {
+        name: String,
+        close: bool,
+        end: usize,
+    },
+}
+
+/// Parses the markup starting with `<` at `start`. Returns `None` if it is a
+/// literal `<`.
+fn parse_markup(source: &str, start: usize) -> Option<Markup> {
+    let rest = &source[start..];
+    let until = |pat: &str, from: usize| {
+        rest[from..]
+            .find(pat)
+            .map_or(source.len(), |p| start + from + p + pat.len())
+    };
+    if rest.starts_with("<!--") {
+        return Some(Markup::Skip(until("-->", 4)));
+    }
+    if rest.starts_with("<![CDATA[") {
+        let next = until("]]>", 9);
+        let end = if source[..next].ends_with("]]>") {
+            next - 3
+        } else {
+            next
+        };
+        return Some(Markup::CData(start + 9, end, next));
+    }
+    if rest.starts_with("<!") || rest.starts_with("<?") {
+        return Some(Markup::Skip(until(">", 2)));
+    }
+    let (close, name_start) = if rest.starts_with("</") {
+        (true, 2)
+    } else {
+        (false, 1)
+    };
+    let name_len = rest[name_start..]
+        .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_' || c == ':' || c == '.'))
+        .unwrap_or(rest.len() - name_start);
+    if !rest[name_start..].starts_with(|c: char| c.is_alphabetic()) {
+        return None;
+    }
+    let name = rest[name_start..name_start + name_len].to_ascii_lowercase();
+    // skip attributes, respecting quoted values that may contain `>`. Quotes
+    // only delimit a value right after `=`, so that e.g. `<p don't>` ends at
+    // its `>`.
+    let mut quote = None;
+    let mut after_eq = false;
+    let attrs_start = name_start + name_len;
+    for (j, c) in rest[attrs_start..].char_indices() {
+        let j = attrs_start + j;
+        match (quote, c) {
+            (None, '"') | (None, '\'') if after_eq => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (Some(_), _) => (),
+            (None, '>') => {
+                return Some(Markup::Tag {
+                    name,
+                    close,
+                    end: start + j + 1,
+                })
+            }
+            (None, c) if c.is_whitespace() => continue,
+            _ => (),
+        }
+        after_eq = quote.is_none() && c == '=';
+    }
+    Some(Markup::Tag {
+        name,
+        close,
+        end: source.len(),
+    })
+}
+
+/// Returns the byte offset just after `</name>`, searching from `from`.
+fn find_closing_tag(source: &str, from: usize, name: &str) -> usize {
+    let lower = source[from..].to_ascii_lowercase();
+    let pat = format!("</{}", name);
+    match lower.find(&pat) {
+        Some(p) => {
+            let p = from + p;
+            source[p..].find('>').map_or(source.len(), |q| p + q + 1)
+        }
+        None => source.len(),
+    }
+}
+
+/// Parses the entity reference starting with `&` at `start`, and returns the
+/// decoded text and the end offset.
+fn parse_entity(source: &str, start: usize) -> Option<(Cow<'static, str>, usize)> {
+    let rest = &source[start + 1..];
+    if let Some(num) = rest.strip_prefix('#') {
+        let (digits, radix, prefix) = match num.strip_prefix('x').or_else(|| num.strip_prefix('X'))
+        {
+            Some(hex) => (hex, 16, 2),
+            None => (num, 10, 1),
+        };
+        let len = digits
+            .find(|c: char| !c.is_digit(radix))
+            .unwrap_or(digits.len());
+        if len == 0 {
+            return None;
+        }
+        // NUL, surrogates and out of range code points become U+FFFD, as in
+        // HTML5
+        let c = u32::from_str_radix(&digits[..len], radix)
+            .ok()
+            .filter(|&code| code != 0)
+            .and_then(std::char::from_u32)
+            .unwrap_or('\u{fffd}');
+        let end = start + 1 + prefix + len;
+        let end = if source[end..].starts_with(';') {
+            end + 1
+        } else {
+            end
+        };
+        return Some((Cow::Owned(c.to_string()), end));
+    }
+    let name_len = rest
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(rest.len());
+    if rest[name_len..].starts_with(';') {
+        if let Some(s) = named_entity(&rest[..name_len + 1]) {
+            return Some((Cow::Borrowed(s), start + name_len + 2));
+        }
+    }
+    // legacy names, the longest match first
+    (1..=name_len)
+        .rev()
+        .find_map(|n| named_entity(&rest[..n]).map(|s| (Cow::Borrowed(s), start + 1 + n)))
+}
+
+/// Looks up `name`, including the trailing `;` if any, in the HTML5 entities.
+fn named_entity(name: &str) -> Option<&'static str> {
+    entities::ENTITIES
+        .binary_search_by_key(&name, |&(k, _)| k)
+        .ok()
+        .map(|i| entities::ENTITIES[i].1)
+}
+
+fn is_block_element(name: &str) -> bool {
+    matches!(
+        name,
+        "address"
+            | "article"
+            | "aside"
+            | "blockquote"
+            | "br"
+            | "dd"
+            | "div"
+            | "dl"
+            | "dt"
+            | "fieldset"
+            | "figcaption"
+            | "figure"
+            | "footer"
+            | "form"
+            | "h1"
+            | "h2"
+            | "h3"
+            | "h4"
+            | "h5"
+            | "h6"
+            | "header"
+            | "hr"
+            | "li"
+            | "main"
+            | "nav"
+            | "ol"
+            | "p"
+            | "pre"
+            | "section"
+            | "table"
+            | "td"
+            | "th"
+            | "tr"
+            | "ul"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest(
+        input,
+        expected,
+        case("", ""),
+        case("plain text", "plain text"),
+        case("<b>bold</b> and <i class=\"a>b\">it</i>", "bold and it"),
+        case("a < b &amp;&amp c", "a < b && c"),
+        case("&rarr;&alpha;&Agrave;&NotEqualTilde;", "→αÀ≂̸"),
+        case("&copy2020 &notit; &ampx &#65&#x42 AT&T", "©2020 ¬it; &x AB AT&T"),
+        case("<日本>x</日本>y", "xy"),
+        case("<a title=\"日本>\">x</a>", "x"),
+        case("&#65;&#x42;&unknown;&#xzz;", "AB&unknown;&#xzz;"),
+        case(
+            "&#0;&#xD800;&#x110000;&#99999999999;&#",
+            "\u{fffd}\u{fffd}\u{fffd}\u{fffd}&#"
+        ),
+        case("<p don't>a</p>b<p title='x>y' it's>c", "abc"),
+        case("<a href = \"x>y\">a</a><a b=c\"d>e\">f", "ae\">f"),
+        case("x<!-- <b>c</b> -->y<?xml version=\"1.0\"?>", "xy"),
+        case("<![CDATA[<raw> &amp;]]>!", "<raw> &amp;!"),
+        case("<script>if (a < b) {}</script>é<STYLE>p {}</STYLE>", "é"),
+        case("unclosed <a href", "unclosed ")
+    )]
+    fn strip_markup_handmade(input: &str, expected: &str) {
+        let ret = strip_markup(input);
+        assert_eq!(ret.text, expected);
+        assert_eq!(ret.mapping.len(), expected.chars().count());
+        assert_eq!(ret.byte_mapping.len(), expected.len());
+    }
+
+    #[test]
+    fn strip_markup_mapping() {
+        let ret = strip_markup("<p>Caf&eacute; &lt;x&gt; &bogus;</p>");
+        assert_eq!(ret.text, "Café <x> &bogus;");
+        assert_eq!(ret.mapping[3], (6..14).collect::<Vec<_>>());
+        let ret = strip_markup("<a>&lt;é</a>");
+        assert_eq!(ret.text, "<é");
+        assert_eq!(ret.mapping, [vec![3, 4, 5, 6], vec![7]]);
+        assert_eq!(ret.byte_mapping, [vec![3, 4, 5, 6], vec![7, 8], vec![7, 8]]);
+        assert_eq!(ret.try_align_byte_spans(&[(1, 3)]), Ok(vec![vec![(7, 9)]]));
+        assert_eq!(
+            ret.try_align_byte_spans(&[(0, 2)]),
+            Err(crate::Error::NotCharBoundary {
+                span: (0, 2),
+                offset: 2
+            })
+        );
+        assert!(ret.try_align_spans(&[(0, 3)]).is_err());
+    }
+
+    #[test]
+    fn strip_markup_block_newlines() {
+        let options = StripOptions {
+            block_newlines: true,
+        };
+        let ret = strip_markup_with_options("<div><p>a</p><p>b<br/>c</p></div>d", &options);
+        assert_eq!(ret.text, "a\nb\nc\nd");
+        assert_eq!(ret.mapping[1], Vec::<usize>::new());
+    }
+}
@@ -0,0 +1,318 @@
+//! Pairs spans of two annotation sets by their overlap, e.g. to compare two
+//! annotators or two model versions.
+use crate::Span;
+
+/// Overlap score of two spans, between 0 and 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Similarity {
+    /// Intersection over union (Jaccard index).
+    Iou,
+    /// Twice the intersection over the sum of the lengths (Sørensen–Dice).
+    Dice,
+}
+
+impl Similarity {
+    /// Returns the score of `a` and `b`. Two empty spans score 1 if they are
+    /// at the same position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use textspan::matching::Similarity;
+    /// assert_eq!(Similarity::Iou.score((0, 4), (2, 6)), 1. / 3.);
+    /// assert_eq!(Similarity::Dice.score((0, 4), (2, 6)), 0.5);
+    /// ```
+    pub fn score(self, (l, r): Span, (l2, r2): Span) -> f64 {
+        if (l, r) == (l2, r2) {
+            return 1.;
+        }
+        let inter = r.min(r2).saturating_sub(l.max(l2)) as f64;
+        let (len, len2) = (r.saturating_sub(l) as f64, r2.saturating_sub(l2) as f64);
+        let denom = match self {
+            Similarity::Iou => len + len2 - inter,
+            Similarity::Dice => (len + len2) / 2.,
+        };
+        if denom == 0. {
+            0.
+        } else {
+            inter / denom
+        }
+    }
+}
+
+/// How pairs are chosen by [`match_spans_with_options`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Assignment {
+    /// Maximizes the sum of the scores of the pairs, with the Hungarian
+    /// algorithm. Takes `O(n^2 m)` time for `n <= m` spans.
+    Optimal,
+    /// Repeatedly pairs the two unpaired spans with the highest score.
+    Greedy,
+}
+
+/// Options for [`match_spans_with_options`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchOptions {
+    pub similarity: Similarity,
+    pub assignment: Assignment,
+    /// Pairs scoring less than this are not made. Pairs scoring 0 are never
+    /// made.
+    pub threshold: f64,
+}
+
+impl Default for MatchOptions {
+    fn default() -> Self {
+        MatchOptions {
+            similarity: Similarity::Iou,
+            assignment: Assignment::Optimal,
+            threshold: 0.,
+        }
+    }
+}
+
+/// Result of [`match_spans`]. Spans are referred to by their indices.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Matching {
+    /// `(index in a, index in b, score)`, sorted by the index in `a`.
+    pub pairs: Vec<(usize, usize, f64)>,
+    pub unmatched_a: Vec<usize>,
+    pub unmatched_b: Vec<usize>,
+}
+
+/// Returns every pair of overlapping spans of `a` and `b` with its score, so
+/// that a span may be paired with several others.
+///
+/// # Examples
+///
+/// ```
+/// use textspan::matching::{overlapping_pairs, Similarity};
+/// let pairs = overlapping_pairs(&[(0, 4)], &[(0, 2), (2, 4), (5, 6)], Similarity::Iou);
+/// assert_eq!(pairs, [(0, 0, 0.5), (0, 1, 0.5)]);
+/// ```
+pub fn overlapping_pairs(
+    a: &[Span],
+    b: &[Span],
+    similarity: Similarity,
+) -> Vec<(usize, usize, f64)> {
+    let mut ret = vec![];
+    for (i, &x) in a.iter().enumerate() {
+        for (j, &y) in b.iter().enumerate() {
+            let score = similarity.score(x, y);
+            if score > 0. {
+                ret.push((i, j, score));
+            }
+        }
+    }
+    ret
+}
+
+/// Pairs spans of `a` with spans of `b` one-to-one, maximizing the sum of
+/// their IoU.
+///
+/// # Examples
+///
+/// ```
+/// let m = textspan::matching::match_spans(&[(0, 10), (0, 2)], &[(0, 9), (2, 10), (20, 21)]);
+/// assert_eq!(m.pairs, [(0, 1, 0.8), (1, 0, 2. / 9.)]);
+/// assert!(m.unmatched_a.is_empty());
+/// assert_eq!(m.unmatched_b, [2]);
+/// ```
+pub fn match_spans(a: &[Span], b: &[Span]) -> Matching {
+    match_spans_with_options(a, b, &MatchOptions::default())
+}
+
+/// Same as [`match_spans`], with the given `options`.
+///
+/// # Examples
+///
+/// ```
+/// use textspan::matching::{match_spans_with_options, Assignment, MatchOptions};
+/// let options = MatchOptions { assignment: Assignment::Greedy, ..Default::default() };
+/// let m = match_spans_with_options(&[(0, 10), (0, 2)], &[(0, 9), (2, 10)], &options);
+/// assert_eq!(m.pairs, [(0, 0, 0.9)]);
+/// assert_eq!(m.unmatched_a, [1]);
+/// ```
+pub fn match_spans_with_options(a: &[Span], b: &[Span], options: &MatchOptions) -> Matching {
+    let mut candidates = overlapping_pairs(a, b, options.similarity);
+    candidates.retain(|&(_, _, score)| score >= options.threshold);
+    let mut pairs = match options.assignment {
+        Assignment::Greedy => {
+            candidates.sort_by(|x, y| {
+                y.2.partial_cmp(&x.2)
+                    .unwrap()
+                    .then((x.0, x.1).cmp(&(y.0, y.1)))
+            });
+            let mut used_a = vec![false; a.len()];
+            let mut used_b = vec![false; b.len()];
+            let mut pairs = vec![];
+            for (i, j, score) in candidates {
+                if !used_a[i] && !used_b[j] {
+                    used_a[i] = true;
+                    used_b[j] = true;
+                    pairs.push((i, j, score));
+                }
+            }
+            pairs
+        }
+        Assignment::Optimal => {
+            let mut scores = vec![vec![0.; b.len()]; a.len()];
+            for &(i, j, score) in &candidates {
+                scores[i][j] = score;
+            }
+            hungarian(&scores)
+                .into_iter()
+                .map(|(i, j)| (i, j, scores[i][j]))
+                .filter(|&(_, _, score)| score > 0.)
+                .collect()
+        }
+    };
+    pairs.sort_by_key(|&(i, j, _)| (i, j));
+    let mut matched_a = vec![false; a.len()];
+    let mut matched_b = vec![false; b.len()];
+    for &(i, j, _) in &pairs {
+        matched_a[i] = true;
+        matched_b[j] = true;
+    }
+    let unmatched = |matched: Vec<bool>| (0..matched.len()).filter(|&i| !matched[i]).collect();
+    Matching {
+        pairs,
+        unmatched_a: unmatched(matched_a),
+        unmatched_b: unmatched(matched_b),
+    }
+}
+
+/// Returns the `(row, column)` pairs of an assignment maximizing the sum of
+/// `scores`, with the Hungarian algorithm.
+fn hungarian(scores: &[Vec<f64>]) -> Vec<(usize, usize)> {
+    let n = scores.len();
+    let m = scores.first().map_or(0, Vec::len);
+    if n == 0 || m == 0 {
+        return vec![];
+    }
+    if n > m {
+        let transposed: Vec<Vec<f64>> = (0..m)
+            .map(|j| (0..n).map(|i| scores[i][j]).collect())
+            .collect();
+        return hungarian(&transposed)
+            .into_iter()
+            .map(|(j, i)| (i, j))
+            .collect();
+    }
+    // minimizes the cost `-score`; rows and columns are 1-based, 0 is a sentinel
+    let cost = |i: usize, j: usize| -scores[i - 1][j - 1];
+    let mut u = vec![0.; n + 1];
+    let mut v = vec![0.; m + 1];
+    let mut p = vec![0; m + 1];
+    let mut way = vec![0; m + 1];
+    for i in 1..=n {
+        p[0] = i;
+        let mut j0 = 0;
+        let mut minv = vec![f64::INFINITY; m + 1];
+        let mut used = vec![false; m + 1];
+        loop {
+            used[j0] = true;
+            let i0 = p[j0];
+            let mut delta = f64::INFINITY;
+            let mut j1 = 0;
+            for j in 1..=m {
+                if !used[j] {
+                    let cur = cost(i0, j) - u[i0] - v[j];
+                    if cur < minv[j] {
+                        minv[j] = cur;
+                        way[j] = j0;
+                    }
+                    if minv[j] < delta {
+                        delta = minv[j];
+                        j1 = j;
+                    }
+                }
+            }
+            for j in 0..=m {
+                if used[j] {
+                    u[p[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    minv[j] -= delta;
+                }
+            }
+            j0 = j1;
+            if p[j0] == 0 {
+                break;
+            }
+        }
+        loop {
+            let j1 = way[j0];
+            p[j0] = p[j1];
+            j0 = j1;
+            if j0 == 0 {
+                break;
+            }
+        }
+    }
+    (1..=m)
+        .filter(|&j| p[j] != 0)
+        .map(|j| (p[j] - 1, j - 1))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn similarity_edge_cases() {
+        assert_eq!(Similarity::Iou.score((1, 1), (1, 1)), 1.);
+        assert_eq!(Similarity::Iou.score((1, 1), (0, 2)), 0.);
+        assert_eq!(Similarity::Dice.score((0, 2), (2, 4)), 0.);
+    }
+
+    #[test]
+    fn threshold_and_empty_inputs() {
+        let options = MatchOptions {
+            threshold: 0.5,
+            ..Default::default()
+        };
+        let m = match_spans_with_options(&[(0, 4), (5, 9)], &[(0, 3), (6, 7)], &options);
+        assert_eq!(m.pairs, [(0, 0, 0.75)]);
+        assert_eq!(m.unmatched_a, [1]);
+        assert_eq!(m.unmatched_b, [1]);
+        let m = match_spans(&[], &[(0, 1)]);
+        assert!(m.pairs.is_empty());
+        assert_eq!(m.unmatched_b, [0]);
+    }
+
+    fn total(m: &Matching) -> f64 {
+        m.pairs.iter().map(|p| p.2).sum()
+    }
+
+    #[quickcheck]
+    fn optimal_is_at_least_greedy(a: Vec<(u8, u8)>, b: Vec<(u8, u8)>) -> bool {
+        let to_spans = |spans: Vec<(u8, u8)>| -> Vec<Span> {
+            spans
+                .into_iter()
+                .take(8)
+                .map(|(x, y)| (x.min(y) as usize, x.max(y) as usize))
+                .collect()
+        };
+        let (a, b) = (to_spans(a), to_spans(b));
+        let optimal = match_spans(&a, &b);
+        let greedy = match_spans_with_options(
+            &a,
+            &b,
+            &MatchOptions {
+                assignment: Assignment::Greedy,
+                ..Default::default()
+            },
+        );
+        let one_to_one = |m: &Matching| {
+            let mut xs: Vec<_> = m.pairs.iter().map(|p| p.1).collect();
+            xs.sort_unstable();
+            xs.dedup();
+            xs.len() == m.pairs.len()
+                && m.pairs.len() + m.unmatched_a.len() == a.len()
+                && m.pairs.len() + m.unmatched_b.len() == b.len()
+        };
+        one_to_one(&optimal) && one_to_one(&greedy) && total(&optimal) >= total(&greedy) - 1e-9
+    }
+}
@@ -0,0 +1,248 @@
+//! Renders labeled spans inline in their text, e.g. `[Barack Obama]PER was
+//! born in [Hawaii]LOC`.
+use crate::{remove_span_overlaps_idx, LabeledSpan, Span};
+
+/// How spans are marked up in the text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Markup {
+    /// `[Barack Obama]PER`. The text is not escaped.
+    Brackets,
+    /// `<PER>Barack Obama</PER>`, with the text escaped. Labels are used as
+    /// tag names as they are.
+    Xml,
+    /// `<mark data-label="PER">Barack Obama</mark>`, with the text escaped.
+    Html,
+}
+
+/// What to do with spans that partially overlap each other, i.e. that cannot
+/// be nested. Spans contained in other spans are always nested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlapPolicy {
+    /// Splits a span at the boundaries of the spans it crosses, rendering it as
+    /// several fragments with the same label.
+    Split,
+    /// Drops a span that crosses a longer (or, if as long, earlier) span.
+    DropCrossing,
+    /// Drops every overlapping span, including nested ones, as
+    /// [`remove_span_overlaps`](crate::remove_span_overlaps) does.
+    Flatten,
+}
+
+/// Options for [`render_spans_with_options`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderOptions {
+    pub markup: Markup,
+    pub overlap: OverlapPolicy,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            markup: Markup::Brackets,
+            overlap: OverlapPolicy::Split,
+        }
+    }
+}
+
+fn crosses((l, r): Span, (l2, r2): Span) -> bool {
+    (l < l2 && l2 < r && r < r2) || (l2 < l && l < r2 && r2 < r)
+}
+
+/// Returns the fragments to render as `(span, index into spans)`, so that
+/// no two of them cross each other.
+fn fragments(spans: &[LabeledSpan], policy: OverlapPolicy) -> Vec<(Span, usize)> {
+    // longer spans first, so that they are kept whole
+    let mut order: Vec<usize> = (0..spans.len()).collect();
+    order.sort_by_key(|&i| {
+        let (l, r) = spans[i].span;
+        (std::cmp::Reverse(r.saturating_sub(l)), l)
+    });
+    let mut accepted: Vec<(Span, usize)> = vec![];
+    match policy {
+        OverlapPolicy::Flatten => {
+            let raw: Vec<Span> = spans.iter().map(|s| s.span).collect();
+            accepted = remove_span_overlaps_idx(&raw)
+                .into_iter()
+                .map(|i| (raw[i], i))
+                .collect();
+        }
+        OverlapPolicy::DropCrossing => {
+            for i in order {
+                let span = spans[i].span;
+                if !accepted.iter().any(|&(a, _)| crosses(span, a)) {
+                    accepted.push((span, i));
+                }
+            }
+        }
+        OverlapPolicy::Split => {
+            for i in order {
+                let mut pending = vec![spans[i].span];
+                while let Some((l, r)) = pending.pop() {
+                    let crossed = accepted.iter().find(|&&(a, _)| crosses((l, r), a));
+                    match crossed {
+                        Some(&((l2, r2), _)) => {
+                            let at = if l < l2 { l2 } else { r2 };
+                            pending.push((l, at));
+                            pending.push((at, r));
+                        }
+                        None => accepted.push(((l, r), i)),
+                    }
+                }
+            }
+        }
+    }
+    // opening order: by start, then outer first
+    accepted.sort_by_key(|&((l, r), i)| (l, std::cmp::Reverse(r), i));
+    accepted
+}
+
+fn escape(s: &str, markup: Markup) -> String {
+    match markup {
+        Markup::Brackets => s.to_string(),
+        Markup::Xml | Markup::Html => s
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;"),
+    }
+}
+
+fn open_tag(label: &str, markup: Markup) -> String {
+    match markup {
+        Markup::Brackets => "[".to_string(),
+        Markup::Xml => format!("<{}>", label),
+        Markup::Html => format!("<mark data-label=\"{}\">", escape(label, markup)),
+    }
+}
+
+fn close_tag(label: &str, markup: Markup) -> String {
+    match markup {
+        Markup::Brackets => format!("]{}", label),
+        Markup::Xml => format!("</{}>", label),
+        Markup::Html => "</mark>".to_string(),
+    }
+}
+
+/// Renders `spans` inline in `text` as bracketed markup, splitting spans that
+/// partially overlap each other.
+///
+/// # Panics
+///
+/// Panics if a span is out of `text`.
+///
+/// # Examples
+///
+/// ```
+/// use textspan::LabeledSpan;
+/// let spans = [LabeledSpan::new(0, 12, "PER"), LabeledSpan::new(0, 6, "FIRST")];
+/// assert_eq!(
+///     textspan::render::render_spans("Barack Obama was born", &spans),
+///     "[[Barack]FIRST Obama]PER was born"
+/// );
+/// ```
+pub fn render_spans(text: &str, spans: &[LabeledSpan]) -> String {
+    render_spans_with_options(text, spans, &RenderOptions::default())
+}
+
+/// Same as [`render_spans`], with the given `options`.
+///
+/// # Examples
+///
+/// ```
+/// use textspan::render::{render_spans_with_options, Markup, OverlapPolicy, RenderOptions};
+/// use textspan::LabeledSpan;
+/// let spans = [LabeledSpan::new(0, 5, "A"), LabeledSpan::new(2, 7, "B")];
+/// let mut options = RenderOptions { markup: Markup::Xml, overlap: OverlapPolicy::Split };
+/// assert_eq!(
+///     render_spans_with_options("abcdefg", &spans, &options),
+///     "<A>ab<B>cde</B></A><B>fg</B>"
+/// );
+/// options.overlap = OverlapPolicy::DropCrossing;
+/// options.markup = Markup::Html;
+/// assert_eq!(
+///     render_spans_with_options("a<cdefg", &spans, &options),
+///     "<mark data-label=\"A\">a&lt;cde</mark>fg"
+/// );
+/// ```
+pub fn render_spans_with_options(
+    text: &str,
+    spans: &[LabeledSpan],
+    options: &RenderOptions,
+) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    for s in spans {
+        assert!(
+            s.span.0 <= s.span.1 && s.span.1 <= chars.len(),
+            "span {:?} is out of the text of length {}",
+            s.span,
+            chars.len()
+        );
+    }
+    let markup = options.markup;
+    let fragments = fragments(spans, options.overlap);
+    let mut ret = String::new();
+    let mut stack: Vec<(Span, usize)> = vec![];
+    let mut next = 0;
+    for pos in 0..=chars.len() {
+        while let Some(&((_, r), i)) = stack.last() {
+            if r > pos {
+                break;
+            }
+            ret.push_str(&close_tag(&spans[i].label, markup));
+            stack.pop();
+        }
+        while next < fragments.len() && fragments[next].0 .0 == pos {
+            let ((_, r), i) = fragments[next];
+            ret.push_str(&open_tag(&spans[i].label, markup));
+            if r == pos {
+                ret.push_str(&close_tag(&spans[i].label, markup));
+            } else {
+                stack.push(fragments[next]);
+            }
+            next += 1;
+        }
+        if let Some(&c) = chars.get(pos) {
+            let mut buf = [0; 4];
+            ret.push_str(&escape(c.encode_utf8(&mut buf), markup));
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest(spans, overlap, expected,
+        case(vec![], OverlapPolicy::Split, "abcdefg"),
+        case(vec![(0, 7, "A")], OverlapPolicy::Split, "[abcdefg]A"),
+        case(vec![(1, 1, "E"), (1, 3, "A")], OverlapPolicy::Split, "a[[]Ebc]Adefg"),
+        case(vec![(0, 3, "A"), (3, 5, "B")], OverlapPolicy::Split, "[abc]A[de]Bfg"),
+        case(vec![(2, 4, "B"), (0, 7, "A"), (2, 4, "C")], OverlapPolicy::Split, "[ab[[cd]C]Befg]A"),
+        case(vec![(0, 4, "A"), (2, 6, "B")], OverlapPolicy::Split, "[ab[cd]B]A[ef]Bg"),
+        case(vec![(0, 3, "A"), (2, 7, "B")], OverlapPolicy::Split, "[ab]A[[c]Adefg]B"),
+        case(vec![(0, 4, "A"), (2, 6, "B")], OverlapPolicy::DropCrossing, "[abcd]Aefg"),
+        case(vec![(0, 7, "A"), (2, 4, "B"), (5, 6, "C")], OverlapPolicy::Flatten, "[abcdefg]A"),
+    )]
+    fn render_brackets(spans: Vec<(usize, usize, &str)>, overlap: OverlapPolicy, expected: &str) {
+        let spans: Vec<_> = spans
+            .into_iter()
+            .map(|(l, r, label)| LabeledSpan::new(l, r, label))
+            .collect();
+        let options = RenderOptions {
+            markup: Markup::Brackets,
+            overlap,
+        };
+        assert_eq!(
+            render_spans_with_options("abcdefg", &spans, &options),
+            expected
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn render_out_of_range() {
+        render_spans("abc", &[LabeledSpan::new(2, 4, "A")]);
+    }
+}
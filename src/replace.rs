@@ -0,0 +1,112 @@
+//! Regex replacement that keeps track of the character correspondence between
+//! the replaced text and the original one.
+//!
+//! Requires the `regex` feature.
+use crate::mapping::{MappedText, MappedTextBuilder};
+use regex::{Regex, Replacer};
+
+/// Replaces all non-overlapping matches of `re` in `text` with `rep`, like
+/// [`Regex::replace_all`], and returns the new text along with its mapping to
+/// `text`.
+///
+/// Unchanged chars map to themselves, and every char of a replacement maps to
+/// the whole match it replaces, so a span covering a placeholder is projected
+/// onto the entire original match.
+///
+/// # Examples
+///
+/// ```
+/// use regex::Regex;
+/// let re = Regex::new(r"https?://\S+").unwrap();
+/// let text = "see https://example.com now";
+/// let replaced = textspan::replace::replace_all(&re, text, "<URL>");
+/// assert_eq!(replaced.text, "see <URL> now");
+/// assert_eq!(replaced.align_spans(&[(4, 9), (10, 13)]), [[(4, 23)], [(24, 27)]]);
+/// ```
+pub fn replace_all<R: Replacer>(re: &Regex, text: &str, rep: R) -> MappedText {
+    replacen(re, text, 0, rep)
+}
+
+/// Replaces at most `limit` non-overlapping matches of `re` in `text` with
+/// `rep`, like [`Regex::replacen`]. If `limit` is 0, all matches are replaced.
+///
+/// See [`replace_all`] for how the mapping is built.
+pub fn replacen<R: Replacer>(re: &Regex, text: &str, limit: usize, mut rep: R) -> MappedText {
+    let mut builder = MappedTextBuilder::new(text);
+    let mut last = 0;
+    let mut buf = String::new();
+    for (i, caps) in re.captures_iter(text).enumerate() {
+        if limit > 0 && i >= limit {
+            break;
+        }
+        let m = caps.get(0).unwrap();
+        builder.copy(last, m.start());
+        buf.clear();
+        rep.replace_append(&caps, &mut buf);
+        builder.push_str(&buf, m.start(), m.end());
+        last = m.end();
+    }
+    builder.copy(last, text.len());
+    builder.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::align_spans_by_mapping;
+
+    #[test]
+    fn replace_all_handmade() {
+        let re = Regex::new(r"\d").unwrap();
+        let ret = replace_all(&re, "ab12c3", "0");
+        assert_eq!(ret.text, "ab00c0");
+        assert_eq!(
+            ret.mapping,
+            [vec![0], vec![1], vec![2], vec![3], vec![4], vec![5]]
+        );
+
+        let re = Regex::new(r"(\w+)@(\w+)").unwrap();
+        let ret = replace_all(&re, "to: bob@ex.", "$2");
+        assert_eq!(ret.text, "to: ex.");
+        assert_eq!(ret.mapping[4], (4..10).collect::<Vec<_>>());
+        assert_eq!(ret.mapping[6], [10]);
+
+        let ret = replace_all(&re, "", "");
+        assert_eq!(ret, MappedText::default());
+    }
+
+    #[test]
+    fn replace_all_deletion_and_insertion() {
+        let re = Regex::new(r"\s+").unwrap();
+        let ret = replace_all(&re, "a  b", "");
+        assert_eq!(ret.text, "ab");
+        assert_eq!(ret.mapping, [[0], [3]]);
+
+        let re = Regex::new(r"^").unwrap();
+        let ret = replace_all(&re, "ab", "> ");
+        assert_eq!(ret.text, "> ab");
+        assert_eq!(ret.mapping, [vec![], vec![], vec![0], vec![1]]);
+    }
+
+    #[test]
+    fn replacen_handmade() {
+        let re = Regex::new(r"\d").unwrap();
+        let ret = replacen(&re, "1234", 2, "x");
+        assert_eq!(ret.text, "xx34");
+    }
+
+    #[test]
+    fn replace_all_byte_mapping() {
+        let re = Regex::new(r"é+").unwrap();
+        let text = "aéébc";
+        let ret = replace_all(&re, text, "E");
+        assert_eq!(ret.text, "aEbc");
+        assert_eq!(ret.byte_mapping.len(), ret.text.len());
+        assert_eq!(ret.byte_mapping[1], [1, 2, 3, 4]);
+        assert_eq!(
+            align_spans_by_mapping(&[(1, 3)], &ret.byte_mapping),
+            [[(1, 6)]]
+        );
+        assert_eq!(&text[1..6], "ééb");
+    }
+}
@@ -0,0 +1,244 @@
+//! Simple tokenizers and sentence splitters that return char spans of the
+//! input text directly, e.g. for [`lift_spans_index`](crate::lift_spans_index)
+//! and [`remove_span_overlaps`](crate::remove_span_overlaps).
+use crate::Span;
+
+/// Converts ascending, non-overlapping byte ranges in `text` to char spans.
+fn to_char_spans(text: &str, byte_spans: impl IntoIterator<Item = (usize, usize)>) -> Vec<Span> {
+    let mut ret = vec![];
+    let (mut byte, mut chars) = (0, 0);
+    for (l, r) in byte_spans {
+        chars += text[byte..l].chars().count();
+        let start = chars;
+        chars += text[l..r].chars().count();
+        byte = r;
+        ret.push((start, chars));
+    }
+    ret
+}
+
+/// Returns the spans of whitespace-separated tokens in `text`.
+///
+/// # Examples
+///
+/// ```
+/// let spans = textspan::tokenize::whitespace_spans(" héllo  world\n");
+/// assert_eq!(spans, [(1, 6), (8, 13)]);
+/// ```
+pub fn whitespace_spans(text: &str) -> Vec<Span> {
+    let mut byte_spans = vec![];
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        match (start, c.is_whitespace()) {
+            (None, false) => start = Some(i),
+            (Some(l), true) => {
+                byte_spans.push((l, i));
+                start = None;
+            }
+            _ => (),
+        }
+    }
+    if let Some(l) = start {
+        byte_spans.push((l, text.len()));
+    }
+    to_char_spans(text, byte_spans)
+}
+
+/// Returns the spans of words in `text` according to the Unicode word
+/// boundaries (UAX #29). Like
+/// [`UnicodeSegmentation::unicode_words`](unicode_segmentation::UnicodeSegmentation::unicode_words),
+/// whitespace and punctuation between words are dropped.
+///
+/// Requires the `segmentation` feature.
+///
+/// # Examples
+///
+/// ```
+/// let spans = textspan::tokenize::word_spans("Hello, can't stop 3.14!");
+/// assert_eq!(spans, [(0, 5), (7, 12), (13, 17), (18, 22)]);
+/// ```
+#[cfg(feature = "segmentation")]
+pub fn word_spans(text: &str) -> Vec<Span> {
+    use unicode_segmentation::UnicodeSegmentation;
+    let byte_spans = text
+        .split_word_bound_indices()
+        .filter(|(_, w)| w.chars().any(char::is_alphanumeric))
+        .map(|(i, w)| (i, i + w.len()));
+    to_char_spans(text, byte_spans)
+}
+
+/// Returns the spans of extended grapheme clusters in `text`.
+///
+/// Requires the `segmentation` feature.
+///
+/// # Examples
+///
+/// ```
+/// let spans = textspan::tokenize::grapheme_spans("e\u{301}a🇯🇵");
+/// assert_eq!(spans, [(0, 2), (2, 3), (3, 5)]);
+/// ```
+#[cfg(feature = "segmentation")]
+pub fn grapheme_spans(text: &str) -> Vec<Span> {
+    use unicode_segmentation::UnicodeSegmentation;
+    let byte_spans = text.grapheme_indices(true).map(|(i, g)| (i, i + g.len()));
+    to_char_spans(text, byte_spans)
+}
+
+/// Abbreviations after which a period does not end a sentence.
+const ABBREVIATIONS: &[&str] = &[
+    "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "vs", "etc", "e.g", "i.e", "cf", "fig",
+    "no", "vol", "inc", "ltd", "co", "corp", "jan", "feb", "mar", "apr", "jun", "jul", "aug",
+    "sep", "sept", "oct", "nov", "dec",
+];
+
+fn is_terminator(c: char) -> bool {
+    matches!(c, '.' | '!' | '?' | '。' | '！' | '？' | '…')
+}
+
+fn is_closing(c: char) -> bool {
+    matches!(
+        c,
+        '"' | '\'' | ')' | ']' | '}' | '»' | '’' | '”' | '」' | '』' | '）'
+    )
+}
+
+/// Returns the spans of sentences in `text` by simple rules, with surrounding
+/// whitespace excluded from each span.
+///
+/// A sentence ends at a run of `.`, `!`, `?` (or their full-width forms)
+/// optionally followed by closing quotes or brackets, if whitespace or the end
+/// of text follows. Periods after common abbreviations (`Mr.`, `e.g.`, ...) and
+/// single-letter initials do not end a sentence. Full-width terminators and
+/// blank lines always end one.
+///
+/// # Examples
+///
+/// ```
+/// let text = "Mr. Smith arrived. Is it 3.5 kg? Yes.\n\nNew paragraph";
+/// let spans = textspan::tokenize::sentence_spans(text);
+/// assert_eq!(spans, [(0, 18), (19, 32), (33, 37), (39, 52)]);
+/// ```
+pub fn sentence_spans(text: &str) -> Vec<Span> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut ret = vec![];
+    let mut start = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let mut end = None;
+        if is_terminator(c) {
+            let mut j = i + 1;
+            while j < chars.len() && (is_terminator(chars[j]) || is_closing(chars[j])) {
+                j += 1;
+            }
+            let full_width = matches!(c, '。' | '！' | '？');
+            let boundary = j == chars.len() || chars[j].is_whitespace();
+            if full_width || (boundary && !(c == '.' && is_abbreviation(&chars[start..i]))) {
+                end = Some(j);
+            }
+            i = j;
+        } else if c == '\n' && i + 1 < chars.len() && chars[i + 1] == '\n' {
+            end = Some(i);
+            i += 2;
+        } else {
+            i += 1;
+        }
+        if let Some(end) = end {
+            push_trimmed(&chars, start, end, &mut ret);
+            start = end;
+        }
+    }
+    push_trimmed(&chars, start, chars.len(), &mut ret);
+    ret
+}
+
+/// Whether the word just before a period at the end of `prefix` is an
+/// abbreviation or an initial.
+fn is_abbreviation(prefix: &[char]) -> bool {
+    let word_start = prefix
+        .iter()
+        .rposition(|c| c.is_whitespace() || *c == '(' || *c == '"')
+        .map_or(0, |p| p + 1);
+    let word: String = prefix[word_start..]
+        .iter()
+        .collect::<String>()
+        .to_lowercase();
+    let mut chars = word.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => c.is_alphabetic(),
+        _ => ABBREVIATIONS.contains(&word.as_str()),
+    }
+}
+
+fn push_trimmed(chars: &[char], mut start: usize, mut end: usize, ret: &mut Vec<Span>) {
+    while start < end && chars[start].is_whitespace() {
+        start += 1;
+    }
+    while end > start && chars[end - 1].is_whitespace() {
+        end -= 1;
+    }
+    if start < end {
+        ret.push((start, end));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest(
+        input,
+        expected,
+        case("", vec![]),
+        case("   ", vec![]),
+        case("a", vec![(0, 1)]),
+        case("\tfoo\u{3000}bar baz ", vec![(1, 4), (5, 8), (9, 12)])
+    )]
+    fn whitespace_spans_handmade(input: &str, expected: Vec<Span>) {
+        assert_eq!(whitespace_spans(input), expected);
+    }
+
+    #[rstest(
+        input,
+        expected,
+        case("", vec![]),
+        case("One sentence", vec![(0, 12)]),
+        case("Hi!! How are you?  Fine...", vec![(0, 4), (5, 17), (19, 26)]),
+        case("See Fig. 3 and J. Doe (e.g. here).", vec![(0, 34)]),
+        case("今日は晴れ。明日は雨？", vec![(0, 6), (6, 11)]),
+        case("v1.2 is out.It works", vec![(0, 20)]),
+        case("line one\n\n\nline two", vec![(0, 8), (11, 19)])
+    )]
+    fn sentence_spans_handmade(input: &str, expected: Vec<Span>) {
+        assert_eq!(sentence_spans(input), expected);
+    }
+
+    #[quickcheck]
+    fn sentence_spans_quick(text: String) {
+        let spans = sentence_spans(&text);
+        let mut cur = 0;
+        for &(l, r) in &spans {
+            assert!(cur <= l && l < r);
+            cur = r;
+        }
+        assert!(cur <= text.chars().count());
+    }
+
+    #[cfg(feature = "segmentation")]
+    #[test]
+    fn word_spans_handmade() {
+        let text = "The quick (\"brown\") fox can't jump 32.3 feet, right?";
+        let spans = word_spans(text);
+        let chars: Vec<_> = text.chars().collect();
+        let words: Vec<String> = spans
+            .iter()
+            .map(|&(l, r)| chars[l..r].iter().collect())
+            .collect();
+        assert_eq!(
+            words,
+            ["The", "quick", "brown", "fox", "can't", "jump", "32.3", "feet", "right"]
+        );
+        assert_eq!(crate::remove_span_overlaps(&spans), spans);
+    }
+}
@@ -0,0 +1,326 @@
+//! Renders the char alignment between two texts, as computed by
+//! [`align_spans`](crate::align_spans), for debugging.
+//!
+//! The texts are shown one above the other, with aligned chars in the same
+//! column. Chars only in `text` are "deleted", chars only in `original_text`
+//! are "inserted", and aligned chars that differ (e.g. by case) are
+//! "changed".
+use crate::{align_spans_by_mapping, Span};
+use std::ops::Range;
+
+/// Number of columns after which the rows are wrapped.
+const WIDTH: usize = 80;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Group {
+    Matched(Range<usize>, Range<usize>),
+    Deleted(usize),
+    Inserted(usize),
+}
+
+/// Splits the two texts into columns of aligned chars.
+fn groups(a2b: &[Vec<usize>], b2a: &[Vec<usize>]) -> Vec<Group> {
+    let (n, m) = (a2b.len(), b2a.len());
+    let (mut i, mut j) = (0, 0);
+    let mut ret = vec![];
+    while i < n || j < m {
+        if i < n && a2b[i].is_empty() {
+            ret.push(Group::Deleted(i));
+            i += 1;
+        } else if j < m && b2a[j].is_empty() || i == n {
+            ret.push(Group::Inserted(j));
+            j += 1;
+        } else if j == m {
+            ret.push(Group::Deleted(i));
+            i += 1;
+        } else {
+            // extend both ranges until no char maps outside of them
+            let (mut ie, mut je) = (i + 1, j + 1);
+            loop {
+                let je2 = a2b[i..ie].iter().flatten().fold(je, |x, &k| x.max(k + 1));
+                let ie2 = b2a[j..je2].iter().flatten().fold(ie, |x, &k| x.max(k + 1));
+                if (ie2, je2) == (ie, je) {
+                    break;
+                }
+                ie = ie2;
+                je = je2;
+            }
+            ret.push(Group::Matched(i..ie, j..je));
+            i = ie;
+            j = je;
+        }
+    }
+    ret
+}
+
+/// Display width of `c` in a terminal. Wide East Asian chars and emoji take
+/// two columns.
+fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x1100..=0x115F
+        | 0x2E80..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+/// Makes whitespace other than a space visible.
+fn visible(c: char) -> char {
+    match c {
+        '\n' => '↵',
+        '\t' => '→',
+        '\r' => '␍',
+        c if c.is_control() => '�',
+        c => c,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Matched,
+    Changed,
+    Deleted,
+    Inserted,
+}
+
+/// One column of the rendered alignment.
+struct Column {
+    top: String,
+    bottom: String,
+    width: usize,
+    kind: Kind,
+    top_highlighted: bool,
+    bottom_highlighted: bool,
+}
+
+fn columns(text: &str, original_text: &str, highlight: Option<Span>) -> (Vec<Column>, String) {
+    let a: Vec<char> = text.chars().collect();
+    let b: Vec<char> = original_text.chars().collect();
+    let (a2b, b2a) = tokenizations::get_charmap(text, original_text);
+    let (source, projected, summary) = match highlight {
+        Some(span) => {
+            let projected = align_spans_by_mapping(&[span], &a2b).remove(0);
+            let summary = format!("span {:?} -> {:?}", span, projected);
+            (Some(span), projected, summary)
+        }
+        None => (None, vec![], String::new()),
+    };
+    let in_source = |i: usize| matches!(source, Some((l, r)) if l <= i && i < r);
+    let in_projected = |j: usize| projected.iter().any(|&(l, r)| l <= j && j < r);
+    let ret = groups(&a2b, &b2a)
+        .into_iter()
+        .map(|group| {
+            let (top, bottom, kind) = match &group {
+                Group::Matched(x, y) => {
+                    let same = a[x.clone()] == b[y.clone()];
+                    let kind = if same { Kind::Matched } else { Kind::Changed };
+                    (x.clone(), y.clone(), kind)
+                }
+                Group::Deleted(i) => (*i..*i + 1, 0..0, Kind::Deleted),
+                Group::Inserted(j) => (0..0, *j..*j + 1, Kind::Inserted),
+            };
+            let width = |cs: &[char]| cs.iter().map(|&c| char_width(c)).sum::<usize>();
+            Column {
+                top: a[top.clone()].iter().map(|&c| visible(c)).collect(),
+                bottom: b[bottom.clone()].iter().map(|&c| visible(c)).collect(),
+                width: width(&a[top.clone()]).max(width(&b[bottom.clone()])),
+                kind,
+                top_highlighted: top.clone().any(in_source),
+                bottom_highlighted: bottom.clone().any(in_projected),
+            }
+        })
+        .collect();
+    (ret, summary)
+}
+
+/// Splits `columns` into rows of at most [`WIDTH`] display columns.
+fn rows(columns: &[Column]) -> Vec<&[Column]> {
+    let mut ret = vec![];
+    let (mut start, mut width) = (0, 0);
+    for (i, c) in columns.iter().enumerate() {
+        if width + c.width > WIDTH && i > start {
+            ret.push(&columns[start..i]);
+            start = i;
+            width = 0;
+        }
+        width += c.width;
+    }
+    if start < columns.len() || ret.is_empty() {
+        ret.push(&columns[start..]);
+    }
+    ret
+}
+
+fn pad(s: &str, width: usize) -> String {
+    let w: usize = s.chars().map(char_width).sum();
+    format!("{}{}", s, " ".repeat(width.saturating_sub(w)))
+}
+
+/// Renders the alignment of `text` to `original_text` as terminal text with
+/// ANSI colors: deleted chars are red, inserted chars are green and changed
+/// chars are yellow. If `highlight` is given, the span of `text` and its
+/// projection onto `original_text` are underlined, and the projected spans
+/// are printed below.
+///
+/// # Examples
+///
+/// ```
+/// let out = textspan::visualize::alignment_ansi("foobar", "FOo. BAR", Some((0, 6)));
+/// // `f` is changed to `F` and highlighted
+/// assert!(out.starts_with("text:     \x1b[33;1;4mf\x1b[0m"));
+/// assert!(out.contains("\noriginal: \x1b[33;1;4mF\x1b[0m"));
+/// assert!(out.ends_with("span (0, 6) -> [(0, 3), (5, 8)]\n"));
+/// ```
+pub fn alignment_ansi(text: &str, original_text: &str, highlight: Option<Span>) -> String {
+    let (columns, summary) = columns(text, original_text, highlight);
+    let cell = |s: &str, c: &Column, highlighted: bool| {
+        let mut codes = vec![];
+        match c.kind {
+            Kind::Matched => {}
+            Kind::Changed => codes.push("33"),
+            Kind::Deleted => codes.push("41"),
+            Kind::Inserted => codes.push("42"),
+        }
+        if highlighted {
+            codes.push("1;4");
+        }
+        let s = pad(s, c.width);
+        if codes.is_empty() {
+            s
+        } else {
+            format!("\x1b[{}m{}\x1b[0m", codes.join(";"), s)
+        }
+    };
+    let mut ret = String::new();
+    for row in rows(&columns) {
+        ret.push_str("text:     ");
+        for c in row {
+            ret.push_str(&cell(&c.top, c, c.top_highlighted));
+        }
+        ret.push_str("\noriginal: ");
+        for c in row {
+            ret.push_str(&cell(&c.bottom, c, c.bottom_highlighted));
+        }
+        ret.push_str("\n\n");
+    }
+    if !summary.is_empty() {
+        ret.push_str(&summary);
+        ret.push('\n');
+    }
+    ret
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const STYLE: &str = "body { font-family: monospace; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+th { text-align: left; padding-right: 1em; }
+td { padding: 0 1px; white-space: pre; text-align: center; }
+.changed { background: #fff3b0; }
+.deleted { background: #ffc0c0; }
+.inserted { background: #c0f0c0; }
+.highlight { outline: 2px solid #3366ff; }";
+
+/// Renders the alignment of `text` to `original_text` as a standalone HTML
+/// page, with the same information as [`alignment_ansi`].
+///
+/// # Examples
+///
+/// ```
+/// let html = textspan::visualize::alignment_html("a<b", "A < b", Some((2, 3)));
+/// assert!(html.starts_with("<!DOCTYPE html>"));
+/// assert!(html.contains("<td class=\"matched highlight\">b</td>"));
+/// assert!(html.contains("span (2, 3) -&gt; [(4, 5)]"));
+/// ```
+pub fn alignment_html(text: &str, original_text: &str, highlight: Option<Span>) -> String {
+    let (columns, summary) = columns(text, original_text, highlight);
+    let cell = |s: &str, c: &Column, highlighted: bool| {
+        let class = match c.kind {
+            Kind::Matched => "matched",
+            Kind::Changed => "changed",
+            Kind::Deleted => "deleted",
+            Kind::Inserted => "inserted",
+        };
+        let highlight = if highlighted { " highlight" } else { "" };
+        format!(
+            "<td class=\"{}{}\">{}</td>",
+            class,
+            highlight,
+            escape_html(s)
+        )
+    };
+    let mut ret = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>textspan alignment</title>\n<style>\n{}\n</style>\n</head>\n<body>\n",
+        STYLE
+    );
+    for row in rows(&columns) {
+        ret.push_str("<table>\n<tr><th>text</th>");
+        for c in row {
+            ret.push_str(&cell(&c.top, c, c.top_highlighted));
+        }
+        ret.push_str("</tr>\n<tr><th>original</th>");
+        for c in row {
+            ret.push_str(&cell(&c.bottom, c, c.bottom_highlighted));
+        }
+        ret.push_str("</tr>\n</table>\n");
+    }
+    if !summary.is_empty() {
+        ret.push_str(&format!("<p>{}</p>\n", escape_html(&summary)));
+    }
+    ret.push_str("</body>\n</html>\n");
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_handmade() {
+        let (a2b, b2a) = tokenizations::get_charmap("ab-c", "xAbc");
+        assert_eq!(
+            groups(&a2b, &b2a),
+            [
+                Group::Inserted(0),
+                Group::Matched(0..1, 1..2),
+                Group::Matched(1..2, 2..3),
+                Group::Deleted(2),
+                Group::Matched(3..4, 3..4),
+            ]
+        );
+        let (a2b, b2a) = tokenizations::get_charmap("", "");
+        assert!(groups(&a2b, &b2a).is_empty());
+    }
+
+    #[test]
+    fn alignment_ansi_columns() {
+        let out = alignment_ansi("ab-c", "xAbc", None);
+        assert_eq!(
+            out,
+            "text:     \x1b[42m \x1b[0m\x1b[33ma\x1b[0mb\x1b[41m-\x1b[0mc\n\
+             original: \x1b[42mx\x1b[0m\x1b[33mA\x1b[0mb\x1b[41m \x1b[0mc\n\n"
+        );
+    }
+
+    #[test]
+    fn alignment_ansi_wraps_wide_chars() {
+        let text = "東".repeat(50);
+        let out = alignment_ansi(&text, &text, None);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0], format!("text:     {}", "東".repeat(40)));
+        assert_eq!(lines[4], format!("original: {}", "東".repeat(10)));
+    }
+}
@@ -0,0 +1,87 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn run(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_textspan"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn convert_conll2003_to_doccano() {
+    let input = "EU B-ORG\nrejects O\n\nTokyo B-LOC\n";
+    let out = run(
+        &["convert", "--from", "conll2003", "--to", "doccano"],
+        input,
+    );
+    assert!(out.status.success());
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        "{\"label\":[[0,2,\"ORG\"],[11,16,\"LOC\"]],\"text\":\"EU rejects\\nTokyo\"}\n"
+    );
+}
+
+#[test]
+fn convert_spacy_json() {
+    let input = r#"[{"paragraphs": [{"sentences": [{"tokens": [
+        {"id": 0, "orth": "I", "ner": "O"},
+        {"id": 1, "orth": "like", "ner": "O"},
+        {"id": 2, "orth": "Tokyo", "ner": "U-GPE", "space": ""}
+    ]}]}]}]"#;
+    let out = run(&["convert", "--from", "spacy-json"], input);
+    assert!(out.status.success());
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        "{\"spans\":[{\"end\":12,\"label\":\"GPE\",\"start\":7}],\"text\":\"I like Tokyo\"}\n"
+    );
+}
+
+#[test]
+fn convert_jsonl_units() {
+    let input = r#"{"text": "😀 Tokyo", "spans": [{"start": 2, "end": 7, "label": "LOC"}]}"#;
+    let out = run(&["convert", "--to-unit", "byte"], input);
+    assert!(out.status.success());
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        "{\"spans\":[{\"end\":10,\"label\":\"LOC\",\"start\":5}],\"text\":\"😀 Tokyo\"}\n"
+    );
+}
+
+#[test]
+fn validate_reports_each_invalid_line() {
+    let input = concat!(
+        r#"{"text": "abc", "spans": [{"start": 0, "end": 1, "label": "A"}]}"#,
+        "\n",
+        r#"{"text": "abc", "spans": [{"start": 2, "end": 4, "label": "A"}]}"#,
+        "\n",
+        r#"{"spans": []}"#,
+        "\n",
+        r#"{"text": "abc", "label": [[0, 2, "A"], [1, 3, "B"]]}"#,
+        "\n",
+    );
+    let out = run(&["validate", "--no-overlaps"], input);
+    assert!(!out.status.success());
+    assert!(out.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8(out.stderr).unwrap(),
+        concat!(
+            "line 2: invalid span (2, 4): not a valid span of the text in Char offsets\n",
+            "line 3: missing text\n",
+            "line 4: spans overlap\n",
+        )
+    );
+    let out = run(&["validate"], input.lines().next().unwrap());
+    assert!(out.status.success());
+    assert!(out.stderr.is_empty());
+}
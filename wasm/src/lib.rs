@@ -0,0 +1,224 @@
+//! WebAssembly bindings of textspan.
+//!
+//! JavaScript strings are indexed by UTF-16 code units, so spans given to and
+//! returned from these functions are in UTF-16 offsets, e.g. `"🍣a"` has the
+//! span `[2, 3]` for `"a"`. They are converted to char offsets internally.
+use serde::{Deserialize, Serialize};
+use textspanrs::Span;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TYPES: &str = r#"
+export type Span = [number, number];
+export interface LiftedIndex {
+    start: number;
+    startExact: boolean;
+    end: number;
+    endExact: boolean;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "Span")]
+    pub type JsSpan;
+    #[wasm_bindgen(typescript_type = "Span[]")]
+    pub type Spans;
+    #[wasm_bindgen(typescript_type = "Span[][]")]
+    pub type NestedSpans;
+    #[wasm_bindgen(typescript_type = "string[]")]
+    pub type Strings;
+    #[wasm_bindgen(typescript_type = "number[]")]
+    pub type Indices;
+    #[wasm_bindgen(typescript_type = "LiftedIndex")]
+    pub type JsLiftedIndex;
+    #[wasm_bindgen(typescript_type = "LiftedIndex[]")]
+    pub type LiftedIndices;
+}
+
+/// UTF-16 offsets of the char boundaries of `text`, including its end.
+fn utf16_offsets(text: &str) -> Vec<usize> {
+    let mut ret = Vec::with_capacity(text.len() + 1);
+    ret.push(0);
+    for c in text.chars() {
+        ret.push(ret.last().unwrap() + c.len_utf16());
+    }
+    ret
+}
+
+/// Converts a span in UTF-16 offsets to char offsets, given the
+/// [`utf16_offsets`] of the text.
+fn to_char_span(offsets: &[usize], (l, r): Span) -> Result<Span, String> {
+    let index = |x: usize| {
+        offsets.binary_search(&x).map_err(|_| {
+            format!(
+                "span [{}, {}] is out of the text or splits a surrogate pair",
+                l, r
+            )
+        })
+    };
+    if l > r {
+        return Err(format!("span [{}, {}] is inverted", l, r));
+    }
+    Ok((index(l)?, index(r)?))
+}
+
+fn to_utf16_spans(offsets: &[usize], spans: Vec<Vec<Span>>) -> Vec<Vec<Span>> {
+    spans
+        .into_iter()
+        .map(|x| {
+            x.into_iter()
+                .map(|(l, r)| (offsets[l], offsets[r]))
+                .collect()
+        })
+        .collect()
+}
+
+/// [`textspanrs::align_spans`] on UTF-16 offsets.
+pub fn align_spans_utf16(
+    spans: &[Span],
+    text: &str,
+    original_text: &str,
+) -> Result<Vec<Vec<Span>>, String> {
+    let offsets = utf16_offsets(text);
+    let spans = spans
+        .iter()
+        .map(|&x| to_char_span(&offsets, x))
+        .collect::<Result<Vec<_>, _>>()?;
+    let ret = textspanrs::align_spans(&spans, text, original_text);
+    Ok(to_utf16_spans(&utf16_offsets(original_text), ret))
+}
+
+/// [`textspanrs::get_original_spans`] on UTF-16 offsets.
+pub fn get_original_spans_utf16(tokens: &[String], original_text: &str) -> Vec<Vec<Span>> {
+    let ret = textspanrs::get_original_spans(tokens, original_text);
+    to_utf16_spans(&utf16_offsets(original_text), ret)
+}
+
+fn from_js<T: for<'de> Deserialize<'de>>(value: JsValue) -> Result<T, JsError> {
+    serde_wasm_bindgen::from_value(value).map_err(|e| JsError::new(&e.to_string()))
+}
+
+fn to_js<T: Serialize + ?Sized, U: JsCast>(value: &T) -> Result<U, JsError> {
+    serde_wasm_bindgen::to_value(value)
+        .map(JsCast::unchecked_into)
+        .map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Converts the spans defined in `text` to those defined in `originalText`.
+///
+/// Throws if a span is out of `text` or splits a surrogate pair.
+#[wasm_bindgen(js_name = alignSpans)]
+pub fn align_spans(
+    spans: Spans,
+    text: &str,
+    #[wasm_bindgen(js_name = originalText)] original_text: &str,
+) -> Result<NestedSpans, JsError> {
+    let spans: Vec<Span> = from_js(spans.into())?;
+    let ret = align_spans_utf16(&spans, text, original_text).map_err(|e| JsError::new(&e))?;
+    to_js(&ret)
+}
+
+/// Returns the spans of `tokens` in `originalText`, based on the shortest
+/// edit script.
+#[wasm_bindgen(js_name = getOriginalSpans)]
+pub fn get_original_spans(
+    tokens: Strings,
+    #[wasm_bindgen(js_name = originalText)] original_text: &str,
+) -> Result<NestedSpans, JsError> {
+    let tokens: Vec<String> = from_js(tokens.into())?;
+    to_js(&get_original_spans_utf16(&tokens, original_text))
+}
+
+/// Removes overlapping spans, keeping the longest of spans starting at the
+/// same position.
+#[wasm_bindgen(js_name = removeSpanOverlaps)]
+pub fn remove_span_overlaps(spans: Spans) -> Result<Spans, JsError> {
+    // only compares offsets, so it does not matter that they are UTF-16
+    let spans: Vec<Span> = from_js(spans.into())?;
+    to_js(&textspanrs::remove_span_overlaps(&spans))
+}
+
+/// Same as `removeSpanOverlaps`, but returns the indices of the remaining
+/// spans.
+#[wasm_bindgen(js_name = removeSpanOverlapsIdx)]
+pub fn remove_span_overlaps_idx(spans: Spans) -> Result<Indices, JsError> {
+    let spans: Vec<Span> = from_js(spans.into())?;
+    to_js(&textspanrs::remove_span_overlaps_idx(&spans))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LiftedIndex {
+    start: usize,
+    start_exact: bool,
+    end: usize,
+    end_exact: bool,
+}
+
+impl From<(Result<usize, usize>, Result<usize, usize>)> for LiftedIndex {
+    fn from((l, r): (Result<usize, usize>, Result<usize, usize>)) -> Self {
+        LiftedIndex {
+            start: l.unwrap_or_else(|x| x),
+            start_exact: l.is_ok(),
+            end: r.unwrap_or_else(|x| x),
+            end_exact: r.is_ok(),
+        }
+    }
+}
+
+/// Converts `span` to the index range `[start, end)` of `targetSpans`
+/// covering it. `targetSpans` must be sorted and not overlapping.
+#[wasm_bindgen(js_name = liftSpanIndex)]
+pub fn lift_span_index(
+    span: JsSpan,
+    #[wasm_bindgen(js_name = targetSpans)] target_spans: Spans,
+) -> Result<JsLiftedIndex, JsError> {
+    let span: Span = from_js(span.into())?;
+    let target_spans: Vec<Span> = from_js(target_spans.into())?;
+    let ret: LiftedIndex = textspanrs::lift_span_index(span, &target_spans).into();
+    to_js(&ret)
+}
+
+/// Same as `liftSpanIndex`, for `spans` sorted by their start.
+#[wasm_bindgen(js_name = liftSpansIndex)]
+pub fn lift_spans_index(
+    spans: Spans,
+    #[wasm_bindgen(js_name = targetSpans)] target_spans: Spans,
+) -> Result<LiftedIndices, JsError> {
+    let spans: Vec<Span> = from_js(spans.into())?;
+    let target_spans: Vec<Span> = from_js(target_spans.into())?;
+    let ret: Vec<LiftedIndex> = textspanrs::lift_spans_index(&spans, &target_spans)
+        .into_iter()
+        .map(LiftedIndex::from)
+        .collect();
+    to_js(&ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utf16_conversion() {
+        let offsets = utf16_offsets("a🍣b");
+        assert_eq!(offsets, [0, 1, 3, 4]);
+        assert_eq!(to_char_span(&offsets, (1, 4)), Ok((1, 3)));
+        assert!(to_char_span(&offsets, (2, 4)).is_err());
+        assert!(to_char_span(&offsets, (0, 5)).is_err());
+        assert!(to_char_span(&offsets, (3, 1)).is_err());
+    }
+
+    #[test]
+    fn align_spans_with_astral_chars() {
+        // the emoji is one char but two UTF-16 code units
+        let ret = align_spans_utf16(&[(0, 2), (2, 5)], "🍣tokyo", "🍣 Tokyo").unwrap();
+        assert_eq!(ret, [vec![(0, 2)], vec![(3, 6)]]);
+        let tokens = ["🍣".to_string(), "tokyo".to_string()];
+        assert_eq!(
+            get_original_spans_utf16(&tokens, "🍣 Tokyo"),
+            [vec![(0, 2)], vec![(3, 8)]]
+        );
+    }
+}
@@ -0,0 +1,78 @@
+//! Run with `wasm-pack test --node`.
+#![cfg(target_arch = "wasm32")]
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use textspan::*;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+
+fn js<T: serde::Serialize + ?Sized>(value: &T) -> JsValue {
+    serde_wasm_bindgen::to_value(value).unwrap()
+}
+
+fn rs<T: DeserializeOwned>(value: impl Into<JsValue>) -> T {
+    serde_wasm_bindgen::from_value(value.into()).unwrap()
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Lifted {
+    start: usize,
+    start_exact: bool,
+    end: usize,
+    end_exact: bool,
+}
+
+#[wasm_bindgen_test]
+fn align_spans_utf16() {
+    let spans = js(&[(0, 2), (2, 5)]).into();
+    let ret: Vec<Vec<(usize, usize)>> = rs(align_spans(spans, "🍣tokyo", "🍣 Tokyo").unwrap());
+    assert_eq!(ret, [vec![(0, 2)], vec![(3, 6)]]);
+}
+
+#[wasm_bindgen_test]
+fn align_spans_rejects_split_surrogate_pair() {
+    let spans = js(&[(1, 2)]).into();
+    assert!(align_spans(spans, "🍣", "🍣").is_err());
+    let spans = js(&[(0, 3)]).into();
+    assert!(align_spans(spans, "🍣", "🍣").is_err());
+}
+
+#[wasm_bindgen_test]
+fn get_original_spans_utf16() {
+    let tokens = js(&["🍣", "tokyo"]).into();
+    let ret: Vec<Vec<(usize, usize)>> = rs(get_original_spans(tokens, "🍣 Tokyo").unwrap());
+    assert_eq!(ret, [vec![(0, 2)], vec![(3, 8)]]);
+}
+
+#[wasm_bindgen_test]
+fn remove_span_overlaps_js() {
+    let spans = [(0, 2), (0, 3), (2, 4), (5, 7)];
+    let ret: Vec<(usize, usize)> = rs(remove_span_overlaps(js(&spans).into()).unwrap());
+    assert_eq!(ret, [(0, 3), (5, 7)]);
+    let ret: Vec<usize> = rs(remove_span_overlaps_idx(js(&spans).into()).unwrap());
+    assert_eq!(ret, [1, 3]);
+}
+
+#[wasm_bindgen_test]
+fn lift_span_index_js() {
+    let targets = [(0, 3), (3, 4), (4, 9), (9, 12)];
+    let ret: Lifted = rs(lift_span_index(js(&(3, 10)).into(), js(&targets).into()).unwrap());
+    assert_eq!(
+        ret,
+        Lifted {
+            start: 1,
+            start_exact: true,
+            end: 4,
+            end_exact: false
+        }
+    );
+    let ret: Vec<Lifted> =
+        rs(lift_spans_index(js(&[(3, 10)]).into(), js(&targets).into()).unwrap());
+    assert_eq!(ret.len(), 1);
+}
+
+#[wasm_bindgen_test]
+fn invalid_input_throws() {
+    assert!(remove_span_overlaps(JsValue::from_str("foo").into()).is_err());
+}